@@ -0,0 +1,18 @@
+// Picks the model embedded into the binary.
+//
+// ATO3_MODEL_PATH: model file to embed, relative to this crate's root when not
+// absolute. Defaults to model.bin next to Cargo.toml, as written by model_builder.
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ATO3_MODEL_PATH");
+
+    let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let model = match std::env::var_os("ATO3_MODEL_PATH") {
+        Some(path) => root.join(path),
+        None => root.join("model.bin"),
+    };
+
+    println!("cargo:rerun-if-changed={}", model.display());
+    println!("cargo:rustc-env=ATO3_EMBEDDED_MODEL={}", model.display());
+}
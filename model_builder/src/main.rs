@@ -1,40 +1,71 @@
 use std::error::Error;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{self, BufWriter, Read, Write};
 use serde::{Serialize, Deserialize};
 use nalgebra::{DMatrix, DVector};
 
+// Feature order shared by training, prediction and the weight report.
+const FEATURE_NAMES: [&str; 5] = ["Bias", "Seats", "Ratio", "Ratio^2", "IsDirect"];
+
+fn features(seats: f64, ratio: f64, is_direct: bool) -> [f64; 5] {
+    let direct_val = if is_direct { 1.0 } else { 0.0 };
+    [1.0, seats, ratio, ratio * ratio, direct_val]
+}
+
+// (seats, ratio, is_direct, target_sum)
+type Sample = (f64, f64, bool, f64);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PolyModel {
     pub weights: Vec<f64>,
 }
 
 impl PolyModel {
-    // Features: [1.0, Seats, Ratio, Ratio^2, IsDirect]
+    // Features: see FEATURE_NAMES
     pub fn predict(&self, seats: f64, ratio: f64, is_direct: bool) -> f64 {
-        let direct_val = if is_direct { 1.0 } else { 0.0 };
-        let features = vec![1.0, seats, ratio, ratio * ratio, direct_val];
-        
-        features.iter().zip(&self.weights).map(|(f, w)| f * w).sum()
+        features(seats, ratio, is_direct).iter().zip(&self.weights).map(|(f, w)| f * w).sum()
     }
 }
 
-fn train_model(samples: &[(f64, f64, bool, f64)]) -> PolyModel {
-    // samples: (seats, ratio, is_direct, target_sum)
+/// Fit health alongside the trained model, for callers that want to inspect it.
+#[derive(Debug, Clone)]
+pub struct TrainReport {
+    pub model: PolyModel,
+    pub r2: f64,
+    pub rmse: f64,
+    pub condition_number: f64,
+    pub singular_values: Vec<f64>,
+    /// Set when the design matrix was nearly rank-deficient and ridge was used instead of OLS.
+    pub ridge_lambda: Option<f64>,
+}
+
+// Smallest/largest singular value below this means collinear features.
+const RANK_DEFICIENCY_RATIO: f64 = 1e-9;
+// Ridge penalty used when falling back from OLS.
+const RIDGE_LAMBDA: f64 = 1e-3;
+
+#[cfg(test)]
+fn train_model(samples: &[Sample]) -> PolyModel {
+    train_with_report(samples).model
+}
+
+fn train_with_report(samples: &[Sample]) -> TrainReport {
+    train_weighted(samples, &vec![1.0; samples.len()])
+}
+
+/// Weighted least squares: each sample's row and target are scaled by sqrt(weight).
+/// The report's r2 and rmse stay unweighted so runs remain comparable.
+fn train_weighted(samples: &[Sample], sample_weights: &[f64]) -> TrainReport {
     let n = samples.len();
-    let m = 5; // Bias, Seats, Ratio, Ratio^2, IsDirect
+    let m = FEATURE_NAMES.len();
 
     let mut x_vals = Vec::with_capacity(n * m);
     let mut y_vals = Vec::with_capacity(n);
 
-    for (seats, ratio, is_direct, target) in samples {
-        x_vals.push(1.0);
-        x_vals.push(*seats);
-        x_vals.push(*ratio);
-        x_vals.push(ratio * ratio);
-        x_vals.push(if *is_direct { 1.0 } else { 0.0 });
-        
-        y_vals.push(*target);
+    for ((seats, ratio, is_direct, target), weight) in samples.iter().zip(sample_weights) {
+        let scale = weight.sqrt();
+        x_vals.extend(features(*seats, *ratio, *is_direct).iter().map(|f| f * scale));
+        y_vals.push(target * scale);
     }
 
     let x = DMatrix::from_row_slice(n, m, &x_vals);
@@ -42,54 +73,602 @@ fn train_model(samples: &[(f64, f64, bool, f64)]) -> PolyModel {
 
     // Solve (X^T * X)^-1 * X^T * Y
     // Using SVD decomposition for stability: OLS
-    let ols = x.svd(true, true).solve(&y, 1e-10).expect("Linear regression failed");
+    let svd = x.clone().svd(true, true);
+    let singular_values: Vec<f64> = svd.singular_values.iter().cloned().collect();
+    let max_sv = singular_values.iter().cloned().fold(0.0, f64::max);
+    let min_sv = singular_values.iter().cloned().fold(f64::INFINITY, f64::min);
+
+    // With fewer rows than features the SVD returns only n singular values; the missing
+    // ones are zero, so the design is rank-deficient just like the collinear case.
+    let min_sv = if singular_values.len() < m { 0.0 } else { min_sv };
+
+    // On collinear data the SVD's least-norm answer is arbitrary along the null space,
+    // so shrink the weights with a small ridge penalty instead.
+    let ridge_lambda = (min_sv <= max_sv * RANK_DEFICIENCY_RATIO).then_some(RIDGE_LAMBDA);
+    let solution = match ridge_lambda {
+        Some(lambda) => {
+            let xt = x.transpose();
+            let gram = &xt * &x + DMatrix::<f64>::identity(m, m) * lambda;
+            gram.cholesky().expect("Ridge system is not positive definite").solve(&(&xt * &y))
+        }
+        None => svd.solve(&y, 1e-10).expect("Linear regression failed"),
+    };
     
-    let weights: Vec<f64> = ols.iter().cloned().collect();
+    let weights: Vec<f64> = solution.iter().cloned().collect();
+    let model = PolyModel { weights };
+
+    TrainReport {
+        r2: r_squared(&model, samples),
+        rmse: rmse(&model, samples),
+        condition_number: max_sv / min_sv,
+        singular_values,
+        ridge_lambda,
+        model,
+    }
+}
+
+/// Drops exact repeats of earlier samples, keeping first occurrences in order.
+/// Returns how many were removed.
+fn dedup_samples(samples: &mut Vec<Sample>) -> usize {
+    let before = samples.len();
+    let mut seen = std::collections::HashSet::new();
+    samples.retain(|&(seats, ratio, is_direct, target)| {
+        seen.insert((seats.to_bits(), ratio.to_bits(), is_direct, target.to_bits()))
+    });
+    before - samples.len()
+}
+
+// Every weight within this of zero means training found nothing to fit.
+const TRIVIAL_WEIGHT: f64 = 1e-9;
+
+fn check_nontrivial(model: &PolyModel) -> Result<(), String> {
+    if model.weights.iter().all(|w| w.abs() < TRIVIAL_WEIGHT) {
+        return Err("training produced a trivial model; check your data".to_string());
+    }
+    Ok(())
+}
+
+// Weight given to samples inside --focus-range relative to the rest.
+const FOCUS_WEIGHT: f64 = 10.0;
+
+fn focus_weights(samples: &[Sample], lo: f64, hi: f64) -> Vec<f64> {
+    samples
+        .iter()
+        .map(|(seats, ..)| if (lo..=hi).contains(seats) { FOCUS_WEIGHT } else { 1.0 })
+        .collect()
+}
 
-    PolyModel { weights }
+fn sum_squared_residuals(model: &PolyModel, samples: &[Sample]) -> f64 {
+    samples
+        .iter()
+        .map(|(seats, ratio, is_direct, target)| (target - model.predict(*seats, *ratio, *is_direct)).powi(2))
+        .sum()
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let file_path = "../data.csv";
-    let file = File::open(file_path)?;
+fn rmse(model: &PolyModel, samples: &[Sample]) -> f64 {
+    (sum_squared_residuals(model, samples) / samples.len() as f64).sqrt()
+}
+
+/// Coefficient of determination of `model` over `samples`.
+fn r_squared(model: &PolyModel, samples: &[Sample]) -> f64 {
+    let mean = samples.iter().map(|s| s.3).sum::<f64>() / samples.len() as f64;
+    let ss_tot: f64 = samples.iter().map(|s| (s.3 - mean).powi(2)).sum();
+    let ss_res = sum_squared_residuals(model, samples);
+
+    if ss_tot == 0.0 {
+        return if ss_res == 0.0 { 1.0 } else { 0.0 };
+    }
+    1.0 - ss_res / ss_tot
+}
+
+struct Options {
+    inputs: Vec<String>,
+    out: String,
+    min_r2: f64,
+    strict: bool,
+    focus_range: Option<(f64, f64)>,
+    quiet: bool,
+    columns: Vec<usize>,
+    baseline: Option<String>,
+    gen_baseline: bool,
+    max_shift: f64,
+    sqlite: Option<String>,
+    query: String,
+    dedup: bool,
+    trials: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            inputs: Vec::new(),
+            out: "../model.bin".to_string(),
+            min_r2: 0.9,
+            strict: false,
+            focus_range: None,
+            quiet: false,
+            columns: ALL_COLUMNS.to_vec(),
+            baseline: None,
+            gen_baseline: false,
+            max_shift: 10.0,
+            sqlite: None,
+            query: DEFAULT_QUERY.to_string(),
+            dedup: false,
+            trials: 0,
+        }
+    }
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut opts = Options::default();
+        let mut columns_given = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--min-r2" => {
+                    opts.min_r2 = args
+                        .next()
+                        .ok_or("--min-r2 requires a number")?
+                        .parse()
+                        .map_err(|_| "--min-r2 must be a number")?;
+                }
+                "--strict" => opts.strict = true,
+                "--quiet" => opts.quiet = true,
+                "--dedup" => opts.dedup = true,
+                "--focus-range" => {
+                    let range = args.next().ok_or("--focus-range requires lo:hi")?;
+                    let (lo, hi) = range.split_once(':').ok_or("--focus-range must look like lo:hi")?;
+                    let lo: f64 = lo.parse().map_err(|_| "--focus-range bounds must be numbers")?;
+                    let hi: f64 = hi.parse().map_err(|_| "--focus-range bounds must be numbers")?;
+                    if lo > hi {
+                        return Err("--focus-range lower bound exceeds upper bound".to_string());
+                    }
+                    opts.focus_range = Some((lo, hi));
+                }
+                "--columns" => {
+                    columns_given = true;
+                    opts.columns = parse_columns(&args.next().ok_or("--columns requires a list of scenarios")?)?;
+                }
+                "--baseline" => opts.baseline = Some(args.next().ok_or("--baseline requires a path")?),
+                "--gen-baseline" => opts.gen_baseline = true,
+                "--max-shift" => {
+                    opts.max_shift = args
+                        .next()
+                        .ok_or("--max-shift requires a percentage")?
+                        .parse()
+                        .map_err(|_| "--max-shift must be a number")?;
+                }
+                "--sqlite" => opts.sqlite = Some(args.next().ok_or("--sqlite requires a database path")?),
+                "--query" => opts.query = args.next().ok_or("--query requires an SQL statement")?,
+                "--trials" => {
+                    opts.trials = args
+                        .next()
+                        .ok_or("--trials requires a count")?
+                        .parse()
+                        .map_err(|_| "--trials must be a whole number")?;
+                }
+                "--out" => opts.out = args.next().ok_or("--out requires a path")?,
+                other if other.starts_with("--") => return Err(format!("unknown argument: {}", other)),
+                path => opts.inputs.push(path.to_string()),
+            }
+        }
+        if opts.gen_baseline && opts.baseline.is_none() {
+            return Err("--gen-baseline requires --baseline <file>".to_string());
+        }
+        if opts.sqlite.is_some() && (!opts.inputs.is_empty() || columns_given) {
+            return Err("--sqlite reads samples from the database; drop the CSV paths and --columns".to_string());
+        }
+        if opts.inputs.is_empty() && opts.sqlite.is_none() {
+            opts.inputs.push("../data.csv".to_string());
+        }
+        Ok(opts)
+    }
+}
+
+// Scenario columns of data.csv after the seats column: (key, ratio, is_direct).
+// Keys match the TUI's SCENARIOS table.
+const SCENARIO_COLUMNS: [(&str, f64, bool); 6] = [
+    ("stopover-same", 1.0, false),
+    ("direct-same", 1.0, true),
+    ("stopover-twice", 2.0, false),
+    ("direct-twice", 2.0, true),
+    ("stopover-thrice", 3.0, false),
+    ("direct-thrice", 3.0, true),
+];
+const ALL_COLUMNS: [usize; 6] = [0, 1, 2, 3, 4, 5];
+
+fn parse_columns(spec: &str) -> Result<Vec<usize>, String> {
+    let mut columns = Vec::new();
+    for key in spec.split(',').map(str::trim) {
+        let column = SCENARIO_COLUMNS
+            .iter()
+            .position(|(name, ..)| name.eq_ignore_ascii_case(key))
+            .ok_or_else(|| format!("--columns: unknown scenario '{}'", key))?;
+        if columns.contains(&column) {
+            return Err(format!("--columns: '{}' listed twice", key));
+        }
+        columns.push(column);
+    }
+    Ok(columns)
+}
+
+/// Warnings for scenarios left out by --columns. model.bin cannot mark them, so
+/// the TUI will still show predictions for them.
+fn untrained_warnings(columns: &[usize]) -> Vec<String> {
+    let missing: Vec<&str> = (0..SCENARIO_COLUMNS.len())
+        .filter(|c| !columns.contains(c))
+        .map(|c| SCENARIO_COLUMNS[c].0)
+        .collect();
+    if missing.is_empty() {
+        return Vec::new();
+    }
+    let mut warnings = vec![format!("not trained on {}; predictions for them are extrapolated", missing.join(", "))];
+    let direct = columns.iter().filter(|&&c| SCENARIO_COLUMNS[c].2).count();
+    if direct == 0 || direct == columns.len() {
+        let (have, lack) = if direct == 0 { ("stopover", "direct") } else { ("direct", "stopover") };
+        warnings.push(format!(
+            "only {} routes were trained, so Bias and IsDirect cannot be told apart; {} predictions are meaningless",
+            have, lack
+        ));
+    }
+    warnings
+}
+
+struct CsvData {
+    headers: csv::StringRecord,
+    rows: usize,
+    samples: Vec<Sample>,
+}
+
+#[cfg(test)]
+fn read_samples<R: Read>(reader: R) -> Result<CsvData, Box<dyn Error>> {
+    read_columns(reader, &ALL_COLUMNS)
+}
+
+/// Like `read_samples`, but the CSV holds only `columns` (indices into SCENARIO_COLUMNS),
+/// in that order, after the seats column.
+fn read_columns<R: Read>(mut reader: R, columns: &[usize]) -> Result<CsvData, Box<dyn Error>> {
+    // Spreadsheet exports often start with a UTF-8 BOM; drop it so the header row matches.
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let content = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+
+    // '#'-prefixed rows are annotations; blank lines are skipped by the reader.
     let mut rdr = csv::ReaderBuilder::new()
         .has_headers(true)
-        .from_reader(file);
+        .comment(Some(b'#'))
+        .flexible(true)
+        .from_reader(content);
 
+    let headers = rdr.headers()?.clone();
+    // Index, seats, then exactly one column per requested scenario.
+    if headers.len() != 2 + columns.len() {
+        return Err(format!(
+            "header has {} columns but --columns expects {} (index, seats and {} scenarios)",
+            headers.len(),
+            2 + columns.len(),
+            columns.len()
+        )
+        .into());
+    }
+    let mut rows = 0;
     let mut samples = Vec::new();
 
     for result in rdr.records() {
         let record = result?;
-        let seats: f64 = record[1].parse()?;
-        
-        // CSV Cols: 
-        // 2: Stopover 1.0
-        // 3: Direct 1.0
-        // 4: Stopover 2.0
-        // 5: Direct 2.0
-        // 6: Stopover 3.0
-        // 7: Direct 3.0
+        rows += 1;
+        let field = |i: usize| -> Result<f64, Box<dyn Error>> {
+            let cell = record.get(i).ok_or_else(|| format!("missing column {} in row {:?}", i, record))?;
+            Ok(cell.trim().parse()?)
+        };
+        let seats = field(1)?;
+        for (offset, &column) in columns.iter().enumerate() {
+            let (_, ratio, is_direct) = SCENARIO_COLUMNS[column];
+            samples.push((seats, ratio, is_direct, field(2 + offset)?));
+        }
+    }
+
+    Ok(CsvData { headers, rows, samples })
+}
 
-        // Ratio 1.0
-        samples.push((seats, 1.0, false, record[2].parse::<f64>()?));
-        samples.push((seats, 1.0, true, record[3].parse::<f64>()?));
+// Each row must yield seats, ratio, is_direct, target in that order.
+const DEFAULT_QUERY: &str = "SELECT seats, ratio, is_direct, target FROM samples";
 
-        // Ratio 2.0
-        samples.push((seats, 2.0, false, record[4].parse::<f64>()?));
-        samples.push((seats, 2.0, true, record[5].parse::<f64>()?));
+#[cfg(feature = "sqlite")]
+fn read_sqlite(path: &str, query: &str) -> Result<Vec<Sample>, Box<dyn Error>> {
+    let conn = rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn read_sqlite(_path: &str, _query: &str) -> Result<Vec<Sample>, Box<dyn Error>> {
+    Err("--sqlite requires model_builder built with the `sqlite` feature".into())
+}
 
-        // Ratio 3.0
-        samples.push((seats, 3.0, false, record[6].parse::<f64>()?));
-        samples.push((seats, 3.0, true, record[7].parse::<f64>()?));
+/// Encodes `model` for model.bin, refusing NaN or infinite weights.
+fn serialize_model(model: &PolyModel) -> Result<Vec<u8>, Box<dyn Error>> {
+    if !model.weights.iter().all(|w| w.is_finite()) {
+        return Err("model contains non-finite weights; training likely failed".into());
     }
+    Ok(bincode::serialize(model)?)
+}
 
-    let model = train_model(&samples);
-    println!("Trained Weights: {:?}", model.weights);
+/// Deserializes `bytes` and checks it predicts bit-for-bit like `model` on a small grid.
+fn verify_round_trip(model: &PolyModel, bytes: &[u8]) -> Result<(), String> {
+    let loaded: PolyModel = bincode::deserialize(bytes)
+        .map_err(|e| format!("round-trip failed: cannot deserialize written model: {}", e))?;
+
+    for (seats, ratio, is_direct) in check_grid() {
+        let expected = model.predict(seats, ratio, is_direct);
+        let actual = loaded.predict(seats, ratio, is_direct);
+        if expected.to_bits() != actual.to_bits() {
+            return Err(format!(
+                "round-trip failed: seats {} ratio {} direct {} predicted {} before and {} after",
+                seats, ratio, is_direct, expected, actual
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// (seats, ratio, is_direct) points used by the round-trip check and baselines.
+fn check_grid() -> Vec<(f64, f64, bool)> {
+    let mut grid = Vec::new();
+    for seats in [0.0, 150.0, 300.0, 550.0, 720.0] {
+        for ratio in [1.0, 2.0, 3.0] {
+            for is_direct in [false, true] {
+                grid.push((seats, ratio, is_direct));
+            }
+        }
+    }
+    grid
+}
+
+/// xorshift64*: enough randomness for resampling, and reproducible from the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % n as u64) as usize
+    }
+}
+
+// Fixed so repeated --trials runs report the same spread.
+const BOOTSTRAP_SEED: u64 = 0x5EED_A703_CA1C;
+
+/// Mean and standard deviation of each `check_grid` prediction across `trials`
+/// models, each trained on a bootstrap resample of `samples`.
+fn bootstrap_spread(samples: &[Sample], trials: usize, seed: u64) -> Vec<(f64, f64)> {
+    let mut rng = Rng(seed);
+    let models: Vec<PolyModel> = (0..trials)
+        .map(|_| {
+            let resample: Vec<Sample> = (0..samples.len()).map(|_| samples[rng.below(samples.len())]).collect();
+            train_with_report(&resample).model
+        })
+        .collect();
+
+    check_grid()
+        .into_iter()
+        .map(|(seats, ratio, is_direct)| {
+            let predictions: Vec<f64> = models.iter().map(|m| m.predict(seats, ratio, is_direct)).collect();
+            let mean = predictions.iter().sum::<f64>() / trials as f64;
+            let variance = predictions.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / trials as f64;
+            (mean, variance.sqrt())
+        })
+        .collect()
+}
+
+/// `bootstrap_spread` output as CSV, one `check_grid` point per row.
+fn spread_csv(spread: &[(f64, f64)]) -> String {
+    let mut csv = String::from("seats,ratio,direct,mean,sd\n");
+    for ((seats, ratio, is_direct), (mean, sd)) in check_grid().into_iter().zip(spread) {
+        csv.push_str(&format!("{},{},{},{:?},{:?}\n", seats, ratio, is_direct as u8, mean, sd));
+    }
+    csv
+}
+
+/// The model's predictions over `check_grid`, as a CSV the next build can compare against.
+fn baseline_csv(model: &PolyModel) -> String {
+    let mut csv = String::from("seats,ratio,direct,prediction\n");
+    for (seats, ratio, is_direct) in check_grid() {
+        let prediction = model.predict(seats, ratio, is_direct);
+        csv.push_str(&format!("{},{},{},{:?}\n", seats, ratio, is_direct as u8, prediction));
+    }
+    csv
+}
 
-    let out_file = File::create("../model.bin")?;
+fn parse_baseline(csv: &str) -> Result<Vec<Sample>, Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+    let mut rows = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let field = |i: usize| -> Result<f64, Box<dyn Error>> {
+            let cell = record.get(i).ok_or_else(|| format!("missing column {} in row {:?}", i, record))?;
+            Ok(cell.trim().parse()?)
+        };
+        rows.push((field(0)?, field(1)?, field(2)? != 0.0, field(3)?));
+    }
+    Ok(rows)
+}
+
+/// Baseline points where `model` moved by more than `max_shift` percent.
+fn baseline_shifts(model: &PolyModel, baseline: &[Sample], max_shift: f64) -> Vec<String> {
+    baseline
+        .iter()
+        .filter_map(|&(seats, ratio, is_direct, before)| {
+            let after = model.predict(seats, ratio, is_direct);
+            let shift = (after - before).abs() / before.abs() * 100.0;
+            (shift > max_shift).then(|| {
+                format!(
+                    "seats {} ratio {} direct {}: {:.2} -> {:.2} ({:.1}%)",
+                    seats, ratio, is_direct, before, after, shift
+                )
+            })
+        })
+        .collect()
+}
+
+// Exit codes, matching the TUI's: 2 for a bad command line, 4 when reading
+// data, training or writing the model fails.
+const EXIT_USAGE: i32 = 2;
+const EXIT_TRAINING: i32 = 4;
+
+fn main() {
+    std::process::exit(cli(std::env::args().skip(1), &mut io::stdout()));
+}
+
+/// Parses `args`, runs, and returns the process exit code.
+fn cli<I: Iterator<Item = String>>(args: I, out: &mut dyn Write) -> i32 {
+    match Options::parse(args) {
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            EXIT_USAGE
+        }
+        Ok(opts) => match run(&opts, out) {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                EXIT_TRAINING
+            }
+        },
+    }
+}
+
+/// Trains and writes the model. Informational output goes to `out` unless `--quiet`.
+fn run(opts: &Options, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut sink = io::sink();
+    let out: &mut dyn Write = if opts.quiet { &mut sink } else { out };
+
+    let mut samples = Vec::new();
+    let mut total_rows = 0;
+    let mut first_headers: Option<(&str, csv::StringRecord)> = None;
+    if let Some(db) = &opts.sqlite {
+        samples = read_sqlite(db, &opts.query).map_err(|e| format!("{}: {}", db, e))?;
+        writeln!(out, "{}: {} samples", db, samples.len())?;
+    }
+    for path in &opts.inputs {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        let data = read_columns(file, &opts.columns).map_err(|e| format!("{}: {}", path, e))?;
+
+        match &first_headers {
+            Some((first_path, headers)) if *headers != data.headers => {
+                return Err(format!("{} has different headers than {}", path, first_path).into());
+            }
+            Some(_) => {}
+            None => first_headers = Some((path, data.headers.clone())),
+        }
+
+        writeln!(out, "{}: {} rows", path, data.rows)?;
+        total_rows += data.rows;
+        samples.extend(data.samples);
+    }
+    if opts.inputs.len() > 1 {
+        writeln!(out, "Total: {} rows", total_rows)?;
+    }
+    if opts.dedup {
+        let dropped = dedup_samples(&mut samples);
+        writeln!(out, "Dropped {} duplicate samples", dropped)?;
+    }
+    if samples.is_empty() {
+        return Err("no data rows to train on".into());
+    }
+    if opts.sqlite.is_none() {
+        for warning in untrained_warnings(&opts.columns) {
+            eprintln!("WARNING: {}", warning);
+        }
+    }
+
+    let report = match opts.focus_range {
+        Some((lo, hi)) => {
+            let unfocused = train_with_report(&samples);
+            let focused = train_weighted(&samples, &focus_weights(&samples, lo, hi));
+            writeln!(out, "Focus range {}..{} seats shifted weights by:", lo, hi)?;
+            for ((name, before), after) in FEATURE_NAMES
+                .iter()
+                .zip(&unfocused.model.weights)
+                .zip(&focused.model.weights)
+            {
+                writeln!(out, "{:<10}: {:+.3}", name, after - before)?;
+            }
+            focused
+        }
+        None => train_with_report(&samples),
+    };
+    let model = &report.model;
+    check_nontrivial(model)?;
+    writeln!(out, "Trained Weights:")?;
+    for (name, weight) in FEATURE_NAMES.iter().zip(&model.weights) {
+        writeln!(out, "{:<10}: {:.3}", name, weight)?;
+    }
+
+    let r2 = report.r2;
+    writeln!(out, "R^2       : {:.4}", r2)?;
+    writeln!(out, "RMSE      : {:.3}", report.rmse)?;
+    writeln!(out, "Condition : {:.3e}", report.condition_number)?;
+    let singular: Vec<String> = report.singular_values.iter().map(|v| format!("{:.3e}", v)).collect();
+    writeln!(out, "Singular  : {}", singular.join(", "))?;
+    if let Some(lambda) = report.ridge_lambda {
+        writeln!(out, "Rank-deficient design; used ridge (lambda={})", lambda)?;
+    }
+    if r2 < opts.min_r2 {
+        eprintln!("WARNING: R^2 {:.4} is below {:.2}; the model fits the data poorly.", r2, opts.min_r2);
+        eprintln!("WARNING: add more data or a higher-degree feature before shipping this model.");
+        if opts.strict {
+            return Err(format!("R^2 {:.4} below threshold {:.2} (--strict)", r2, opts.min_r2).into());
+        }
+    }
+
+    if opts.trials > 0 {
+        writeln!(out, "Bootstrap over {} resamples (mean +/- sd):", opts.trials)?;
+        let spread = bootstrap_spread(&samples, opts.trials, BOOTSTRAP_SEED);
+        for ((seats, ratio, is_direct), (mean, sd)) in check_grid().into_iter().zip(&spread) {
+            let route = if is_direct { "direct" } else { "stopover" };
+            writeln!(out, "{:>4} seats ratio {} {:<8}: {:>9.2} +/- {:.2}", seats, ratio, route, mean, sd)?;
+        }
+        // model.bin stays a single weight vector the TUI can read; the spread goes beside it.
+        let spread_path = format!("{}.spread.csv", opts.out);
+        std::fs::write(&spread_path, spread_csv(&spread)).map_err(|e| format!("{}: {}", spread_path, e))?;
+        writeln!(out, "Spread saved to {}", spread_path)?;
+    }
+
+    match (&opts.baseline, opts.gen_baseline) {
+        (Some(path), true) => {
+            std::fs::write(path, baseline_csv(model)).map_err(|e| format!("{}: {}", path, e))?;
+            writeln!(out, "Baseline written to {}", path)?;
+        }
+        (Some(path), false) => {
+            let csv = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            let baseline = parse_baseline(&csv).map_err(|e| format!("{}: {}", path, e))?;
+            let shifts = baseline_shifts(model, &baseline, opts.max_shift);
+            if shifts.is_empty() {
+                writeln!(out, "Baseline OK: no prediction moved more than {}%", opts.max_shift)?;
+            } else {
+                eprintln!("WARNING: {} predictions moved more than {}% from {}:", shifts.len(), opts.max_shift, path);
+                for shift in &shifts {
+                    eprintln!("WARNING:   {}", shift);
+                }
+                if opts.strict {
+                    return Err(format!("predictions moved more than {}% from baseline (--strict)", opts.max_shift).into());
+                }
+            }
+        }
+        (None, _) => {}
+    }
+
+    let bytes = serialize_model(model)?;
+    let out_file = File::create(&opts.out)?;
     let mut writer = BufWriter::new(out_file);
-    bincode::serialize_into(&mut writer, &model)?;
-    println!("Model saved to ../model.bin");
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    writeln!(out, "Model saved to {}", opts.out)?;
+
+    verify_round_trip(model, &std::fs::read(&opts.out)?)?;
+    writeln!(out, "round-trip OK")?;
 
     Ok(())
 }
@@ -98,6 +677,33 @@ fn main() -> Result<(), Box<dyn Error>> {
 mod tests {
     use super::*;
 
+    // Exact OLS solution for tests/fixtures/golden.csv, in FEATURE_NAMES order.
+    // Fractions: 86075/156, 101/104, -725/12, 425/12, 425/3.
+    const GOLDEN_WEIGHTS: [f64; 5] = [
+        551.7628205128206,
+        0.9711538461538461,
+        -60.416666666666664,
+        35.416666666666664,
+        141.66666666666666,
+    ];
+
+    /// Asserts each step between successive predictions grows on the previous one
+    /// by at least `min_rel` of its size, so the check holds whatever the units.
+    fn assert_accelerating(preds: &[f64], min_rel: f64) {
+        let diffs: Vec<f64> = preds.windows(2).map(|w| w[1] - w[0]).collect();
+        for (i, step) in diffs.windows(2).enumerate() {
+            assert!(
+                step[1] - step[0] >= min_rel * step[0].abs(),
+                "step {} -> {} grew from {} to {}, less than {}x",
+                i,
+                i + 1,
+                step[0],
+                step[1],
+                1.0 + min_rel
+            );
+        }
+    }
+
     #[test]
     fn test_model_monotonicity_ratio() {
         // Create a dummy model (or train on small data)
@@ -120,8 +726,403 @@ mod tests {
         assert!(p3 > p2);
         
         // Verify non-linear jump (1.0->2.0 is +50, 2.0->3.0 is +100)
-        let diff1 = p2 - p1;
-        let diff2 = p3 - p2;
-        assert!((diff2 - diff1).abs() > 10.0); // Expect acceleration
+        assert_accelerating(&[p1, p2, p3], 0.5);
+    }
+
+    #[test]
+    fn test_r_squared() {
+        // Enough distinct rows for a full-rank design, all on 900 + seats + 100*direct.
+        let samples = vec![
+            (100.0, 1.0, false, 1000.0),
+            (200.0, 1.0, false, 1100.0),
+            (300.0, 1.0, true, 1300.0),
+            (200.0, 2.0, false, 1100.0),
+            (300.0, 3.0, true, 1300.0),
+            (400.0, 3.0, false, 1300.0),
+        ];
+        let model = train_model(&samples);
+        assert!((r_squared(&model, &samples) - 1.0).abs() < 1e-9);
+
+        let trivial = PolyModel { weights: vec![0.0; 5] };
+        assert!(r_squared(&trivial, &samples) < 0.0);
+    }
+
+    #[test]
+    fn test_train_with_report_fields() {
+        let csv = "\
+,Seats,S1,D1,S2,D2,S3,D3
+,550,1050,1200,1100,1250,1200,1350
+,400,900,1100,1000,1150,1100,1250
+,300,850,1000,900,1050,1000,1150
+,200,725,800,750,850,850,1050
+";
+        let samples = read_samples(csv.as_bytes()).unwrap().samples;
+        let report = train_with_report(&samples);
+
+        assert_eq!(report.model.weights.len(), FEATURE_NAMES.len());
+        assert_eq!(report.singular_values.len(), FEATURE_NAMES.len());
+        assert!(report.r2 > 0.9 && report.r2 <= 1.0);
+        assert!(report.rmse > 0.0 && report.rmse.is_finite());
+        assert!(report.condition_number >= 1.0 && report.condition_number.is_finite());
+    }
+
+    #[test]
+    fn test_golden_fixture_weights() {
+        let fixture = include_str!("../tests/fixtures/golden.csv");
+        let samples = read_samples(fixture.as_bytes()).unwrap().samples;
+        let model = train_model(&samples);
+
+        for ((name, got), want) in FEATURE_NAMES.iter().zip(&model.weights).zip(GOLDEN_WEIGHTS) {
+            assert!((got - want).abs() < 1e-6, "{}: got {}, want {}", name, got, want);
+        }
+    }
+
+    #[test]
+    fn test_focus_range_improves_local_fit() {
+        // Quadratic in seats, so a linear seats term cannot fit everywhere at once.
+        let mut samples = Vec::new();
+        for seats in (100..=400).step_by(50) {
+            let seats = seats as f64;
+            for ratio in [1.0, 2.0, 3.0] {
+                for is_direct in [false, true] {
+                    let target = seats * seats / 100.0 + 50.0 * ratio + if is_direct { 100.0 } else { 0.0 };
+                    samples.push((seats, ratio, is_direct, target));
+                }
+            }
+        }
+        let in_range: Vec<Sample> = samples.iter().cloned().filter(|s| (150.0..=250.0).contains(&s.0)).collect();
+
+        let unfocused = train_with_report(&samples);
+        let focused = train_weighted(&samples, &focus_weights(&samples, 150.0, 250.0));
+
+        assert!(rmse(&focused.model, &in_range) < rmse(&unfocused.model, &in_range));
+        assert!(focused.rmse >= unfocused.rmse);
+    }
+
+    #[test]
+    fn test_quiet_run_writes_model_silently() {
+        let out_path = std::env::temp_dir().join(format!("ato3cal_quiet_{}.bin", std::process::id()));
+        let mut opts = Options::default();
+        opts.inputs.push("tests/fixtures/golden.csv".to_string());
+        opts.out = out_path.to_string_lossy().into_owned();
+
+        let mut stdout = Vec::new();
+        opts.quiet = true;
+        run(&opts, &mut stdout).unwrap();
+        assert!(stdout.is_empty());
+        assert!(out_path.exists());
+
+        opts.quiet = false;
+        run(&opts, &mut stdout).unwrap();
+        assert!(!stdout.is_empty());
+
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[test]
+    fn test_round_trip_catches_corruption() {
+        let model = PolyModel { weights: vec![551.7, 0.97, -60.4, 35.4, 141.6] };
+        let mut bytes = bincode::serialize(&model).unwrap();
+        assert!(verify_round_trip(&model, &bytes).is_ok());
+
+        // Flip a bit in the last weight, after the 8-byte length prefix.
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        assert!(verify_round_trip(&model, &bytes).is_err());
+
+        bytes.truncate(10);
+        assert!(verify_round_trip(&model, &bytes).is_err());
+    }
+
+    #[test]
+    fn test_rank_deficient_design_falls_back_to_ridge() {
+        // A single ratio makes the bias, ratio and ratio^2 columns identical.
+        let mut samples = Vec::new();
+        for seats in (100..=400).step_by(50) {
+            let seats = seats as f64;
+            samples.push((seats, 1.0, false, 500.0 + 2.0 * seats));
+            samples.push((seats, 1.0, true, 600.0 + 2.0 * seats));
+        }
+
+        let report = train_with_report(&samples);
+        assert_eq!(report.ridge_lambda, Some(RIDGE_LAMBDA));
+        for (seats, ratio, is_direct, target) in &samples {
+            assert!((report.model.predict(*seats, *ratio, *is_direct) - target).abs() < 1.0);
+        }
+
+        let fixture = include_str!("../tests/fixtures/golden.csv");
+        let full_rank = read_samples(fixture.as_bytes()).unwrap().samples;
+        assert_eq!(train_with_report(&full_rank).ridge_lambda, None);
+    }
+
+    #[test]
+    fn test_fewer_samples_than_features_falls_back_to_ridge() {
+        // Three distinct rows, no duplicated columns, but five features.
+        let samples = vec![
+            (100.0, 1.0, false, 700.0),
+            (200.0, 2.0, true, 900.0),
+            (300.0, 3.0, false, 1100.0),
+        ];
+
+        let report = train_with_report(&samples);
+        assert!(report.singular_values.len() < FEATURE_NAMES.len());
+        assert_eq!(report.ridge_lambda, Some(RIDGE_LAMBDA));
+        assert!(report.condition_number.is_infinite());
+        assert!(report.model.weights.iter().all(|w| w.is_finite()));
+    }
+
+    #[test]
+    fn test_read_samples_tolerates_bom_and_trailing_newlines() {
+        let clean = ",Seats,S1,D1,S2,D2,S3,D3\n,550,1050,1200,1100,1250,1200,1350\n,200,725,800,750,850,850,1050";
+        let exported = format!("\u{FEFF}{}\r\n\r\n", clean.replace('\n', "\r\n"));
+
+        let expected = read_samples(clean.as_bytes()).unwrap();
+        let data = read_samples(exported.as_bytes()).unwrap();
+        assert_eq!(data.headers, expected.headers);
+        assert_eq!(data.rows, 2);
+        assert_eq!(data.samples, expected.samples);
+        assert_eq!(train_model(&data.samples).weights.len(), FEATURE_NAMES.len());
+    }
+
+    #[test]
+    fn test_read_samples_skips_comments_and_blank_lines() {
+        let clean = "\
+,Seats,S1,D1,S2,D2,S3,D3
+,550,1050,1200,1100,1250,1200,1350
+,400,900,1100,1000,1150,1100,1250
+,300,850,1000,900,1050,1000,1150
+,200,725,800,750,850,850,1050
+";
+        let annotated = "\
+,Seats,S1,D1,S2,D2,S3,D3
+# measured before the seating patch
+,550,1050,1200,1100,1250,1200,1350
+,400,900,1100,1000,1150,1100,1250
+
+,300,850,1000,900,1050,1000,1150
+,200,725,800,750,850,850,1050
+";
+        let clean_samples = read_samples(clean.as_bytes()).unwrap().samples;
+        let annotated_samples = read_samples(annotated.as_bytes()).unwrap().samples;
+        assert_eq!(clean_samples, annotated_samples);
+
+        let model = train_model(&annotated_samples);
+        assert_eq!(model.weights, train_model(&clean_samples).weights);
+    }
+
+    #[test]
+    fn test_columns_train_on_direct_only_csv() {
+        let columns = parse_columns("direct-same,direct-twice,direct-thrice").unwrap();
+        assert!(parse_columns("direct-same,direct-same").is_err());
+        assert!(parse_columns("sideways").is_err());
+
+        // Generated from 500 + seats + 20r + 10r^2 + 100 for direct routes.
+        let csv = "\
+,Seats,D1,D2,D3
+,550,1180,1230,1300
+,400,1030,1080,1150
+,200,830,880,950
+";
+        let data = read_columns(csv.as_bytes(), &columns).unwrap();
+        assert_eq!(data.samples.len(), 9);
+        assert!(data.samples.iter().all(|&(_, _, is_direct, _)| is_direct));
+
+        // A full-width file read with fewer columns would silently train on the wrong ones.
+        let full = include_str!("../tests/fixtures/golden.csv");
+        match read_columns(full.as_bytes(), &columns) {
+            Err(err) => assert!(err.to_string().contains("expects 5"), "{}", err),
+            Ok(_) => panic!("full-width file accepted for three columns"),
+        }
+        let narrow = "\
+,Seats,D1,D2
+,550,1180,1230
+";
+        assert!(read_columns(narrow.as_bytes(), &columns).is_err());
+
+        let warnings = untrained_warnings(&columns);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("stopover-same, stopover-twice, stopover-thrice"));
+        assert!(warnings[1].contains("stopover predictions are meaningless"));
+        assert!(untrained_warnings(&ALL_COLUMNS).is_empty());
+        assert_eq!(untrained_warnings(&parse_columns("stopover-same,direct-same").unwrap()).len(), 1);
+
+        // IsDirect is constant here, so the fit is rank-deficient but still reproduces the data.
+        let report = train_with_report(&data.samples);
+        assert!(report.ridge_lambda.is_some());
+        for (seats, ratio, is_direct, target) in &data.samples {
+            assert!((report.model.predict(*seats, *ratio, *is_direct) - target).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_baseline_shift_threshold() {
+        let model = PolyModel { weights: GOLDEN_WEIGHTS.to_vec() };
+        let baseline = parse_baseline(&baseline_csv(&model)).unwrap();
+        assert_eq!(baseline.len(), check_grid().len());
+        assert!(baseline_shifts(&model, &baseline, 0.0).is_empty());
+
+        // Bias +5 moves every prediction by well under 5% of ~550+.
+        let mut nudged = model.clone();
+        nudged.weights[0] += 5.0;
+        assert!(baseline_shifts(&nudged, &baseline, 5.0).is_empty());
+
+        // Doubling the seats term shifts the large-seat points far past 5%.
+        let mut shifted = model.clone();
+        shifted.weights[1] *= 2.0;
+        let shifts = baseline_shifts(&shifted, &baseline, 5.0);
+        assert!(!shifts.is_empty());
+        assert!(shifts.iter().all(|s| !s.starts_with("seats 0 ")));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_read_sqlite_matches_csv_samples() {
+        let fixture = std::fs::read_to_string("tests/fixtures/golden.csv").unwrap();
+        let expected = read_samples(fixture.as_bytes()).unwrap().samples;
+
+        let db_path = std::env::temp_dir().join(format!("ato3cal_samples_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&db_path);
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute("CREATE TABLE samples (seats REAL, ratio REAL, is_direct INTEGER, target REAL)", []).unwrap();
+        for (seats, ratio, is_direct, target) in &expected {
+            conn.execute("INSERT INTO samples VALUES (?1, ?2, ?3, ?4)", rusqlite::params![seats, ratio, is_direct, target])
+                .unwrap();
+        }
+        drop(conn);
+
+        let db = db_path.to_string_lossy();
+        assert_eq!(read_sqlite(&db, DEFAULT_QUERY).unwrap(), expected);
+        assert!(read_sqlite(&db, "SELECT seats FROM samples").is_err());
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[test]
+    fn test_dedup_drops_only_exact_repeats() {
+        let mut samples = vec![
+            (300.0, 1.0, false, 850.0),
+            (300.0, 1.0, true, 1000.0),
+            (300.0, 1.0, false, 850.0),
+            (300.0, 1.0, false, 850.0000001),
+            (300.0, 1.0, true, 1000.0),
+        ];
+        assert_eq!(dedup_samples(&mut samples), 2);
+        assert_eq!(
+            samples,
+            vec![(300.0, 1.0, false, 850.0), (300.0, 1.0, true, 1000.0), (300.0, 1.0, false, 850.0000001)]
+        );
+    }
+
+    #[test]
+    fn test_builds_are_byte_identical() {
+        // model.bin is only the weights; nothing time-dependent goes in.
+        let paths: Vec<_> = (0..2)
+            .map(|i| std::env::temp_dir().join(format!("ato3cal_repro_{}_{}.bin", std::process::id(), i)))
+            .collect();
+        for path in &paths {
+            let mut opts = Options::default();
+            opts.inputs.push("tests/fixtures/golden.csv".to_string());
+            opts.out = path.to_string_lossy().into_owned();
+            opts.quiet = true;
+            run(&opts, &mut io::sink()).unwrap();
+        }
+        assert_eq!(std::fs::read(&paths[0]).unwrap(), std::fs::read(&paths[1]).unwrap());
+        for path in &paths {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_all_zero_targets_are_rejected() {
+        let samples: Vec<Sample> = [100.0, 200.0, 300.0]
+            .iter()
+            .flat_map(|&seats| [(seats, 1.0, false, 0.0), (seats, 2.0, true, 0.0), (seats, 3.0, false, 0.0)])
+            .collect();
+        let err = check_nontrivial(&train_model(&samples)).unwrap_err();
+        assert_eq!(err, "training produced a trivial model; check your data");
+
+        assert!(check_nontrivial(&PolyModel { weights: GOLDEN_WEIGHTS.to_vec() }).is_ok());
+    }
+
+    #[test]
+    fn test_bootstrap_spread_shrinks_with_redundant_data() {
+        let fixture = std::fs::read_to_string("tests/fixtures/golden.csv").unwrap();
+        let samples = read_samples(fixture.as_bytes()).unwrap().samples;
+        let redundant: Vec<Sample> = samples.iter().cycle().take(samples.len() * 8).cloned().collect();
+
+        let mean_sd = |spread: Vec<(f64, f64)>| spread.iter().map(|(_, sd)| sd).sum::<f64>() / spread.len() as f64;
+        let sparse = mean_sd(bootstrap_spread(&samples, 200, BOOTSTRAP_SEED));
+        let dense = mean_sd(bootstrap_spread(&redundant, 200, BOOTSTRAP_SEED));
+        assert!(dense < sparse, "{} should be below {}", dense, sparse);
+
+        let spread = bootstrap_spread(&samples, 20, BOOTSTRAP_SEED);
+        let csv = spread_csv(&spread);
+        assert_eq!(csv.lines().count(), check_grid().len() + 1);
+        assert_eq!(csv.lines().nth(1).unwrap(), format!("0,1,0,{:?},{:?}", spread[0].0, spread[0].1));
+    }
+
+    #[test]
+    fn test_non_finite_weights_are_not_serialized() {
+        let mut model = PolyModel { weights: GOLDEN_WEIGHTS.to_vec() };
+        assert!(serialize_model(&model).is_ok());
+
+        model.weights[2] = f64::NAN;
+        let err = serialize_model(&model).unwrap_err();
+        assert_eq!(err.to_string(), "model contains non-finite weights; training likely failed");
+
+        model.weights[2] = f64::INFINITY;
+        assert!(serialize_model(&model).is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>().into_iter();
+        let out_path = std::env::temp_dir().join(format!("ato3cal_exit_{}.bin", std::process::id()));
+        let out_path = out_path.to_string_lossy();
+        let empty_path = std::env::temp_dir().join(format!("ato3cal_empty_{}.csv", std::process::id()));
+        std::fs::write(&empty_path, ",Seats,S1,D1,S2,D2,S3,D3\n# nothing measured yet\n").unwrap();
+        let mut out = Vec::new();
+
+        assert_eq!(cli(args(&["--bogus"]), &mut out), EXIT_USAGE);
+        assert_eq!(cli(args(&["--min-r2"]), &mut out), EXIT_USAGE);
+        assert_eq!(cli(args(&["/nonexistent-dir/data.csv", "--out", &out_path]), &mut out), EXIT_TRAINING);
+        assert_eq!(cli(args(&[&empty_path.to_string_lossy(), "--out", &out_path]), &mut out), EXIT_TRAINING);
+        assert_eq!(
+            cli(args(&["tests/fixtures/golden.csv", "--out", &out_path, "--min-r2", "1.1", "--strict"]), &mut out),
+            EXIT_TRAINING
+        );
+        assert_eq!(cli(args(&["tests/fixtures/golden.csv", "--out", &out_path]), &mut out), 0);
+
+        std::fs::remove_file(empty_path).unwrap();
+        std::fs::remove_file(out_path.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn test_run_reports_ridge_fallback() {
+        let csv_path = std::env::temp_dir().join(format!("ato3cal_direct_{}.csv", std::process::id()));
+        let out_path = std::env::temp_dir().join(format!("ato3cal_direct_{}.bin", std::process::id()));
+        std::fs::write(&csv_path, ",Seats,D1,D2,D3\n,550,1180,1230,1300\n,400,1030,1080,1150\n,200,830,880,950\n").unwrap();
+        let mut opts = Options::default();
+        opts.inputs.push(csv_path.to_string_lossy().into_owned());
+        opts.out = out_path.to_string_lossy().into_owned();
+        opts.columns = parse_columns("direct-same,direct-twice,direct-thrice").unwrap();
+
+        let mut stdout = Vec::new();
+        run(&opts, &mut stdout).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains(&format!("Rank-deficient design; used ridge (lambda={})", RIDGE_LAMBDA)));
+
+        std::fs::remove_file(csv_path).unwrap();
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[test]
+    fn test_sqlite_rejects_csv_inputs_and_columns() {
+        let parse = |list: &[&str]| Options::parse(list.iter().map(|a| a.to_string()));
+        assert!(parse(&["--sqlite", "db.sqlite", "data.csv"]).is_err());
+        assert!(parse(&["--sqlite", "db.sqlite", "--columns", "direct-same"]).is_err());
+
+        let opts = parse(&["--sqlite", "db.sqlite"]).unwrap();
+        assert!(opts.inputs.is_empty());
+        assert_eq!(parse(&[]).unwrap().inputs, ["../data.csv"]);
     }
 }
@@ -1,7 +1,13 @@
+use std::collections::VecDeque;
 use std::error::Error;
-use std::io;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant, SystemTime};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event,
+        KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -10,43 +16,594 @@ use serde::{Deserialize, Serialize};
 use evalexpr::eval;
 
 // --- Model Definitions ---
+
+// Feature order used by model_builder when training; must stay in sync with it.
+const FEATURE_NAMES: [&str; 5] = ["Bias", "Seats", "Ratio", "Ratio^2", "IsDirect"];
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PolyModel {
     pub weights: Vec<f64>,
 }
 
 impl PolyModel {
+    /// All-zero weights predict 0 everywhere; model_builder refuses to write such a model.
+    fn is_trivial(&self) -> bool {
+        self.weights.iter().all(|w| *w == 0.0)
+    }
+
     pub fn predict(&self, seats: f64, ratio: f64, is_direct: bool) -> f64 {
         let direct_val = if is_direct { 1.0 } else { 0.0 };
-        if self.weights.len() < 5 { return 0.0; }
+        if self.weights.len() < FEATURE_NAMES.len() { return 0.0; }
         
         let w = &self.weights;
-        let p = w[0] * 1.0 
-              + w[1] * seats 
-              + w[2] * ratio 
-              + w[3] * ratio * ratio 
-              + w[4] * direct_val;
-        p
+        w[0] * 1.0 
+            + w[1] * seats 
+            + w[2] * ratio 
+            + w[3] * ratio * ratio 
+            + w[4] * direct_val
+    }
+}
+
+// The six route scenarios, in data.csv column order.
+struct Scenario {
+    key: &'static str,
+    // Bar label in the scenario chart.
+    code: &'static str,
+    label: &'static str,
+    ratio: f64,
+    is_direct: bool,
+}
+
+const SCENARIOS: [Scenario; 6] = [
+    Scenario { key: "stopover-same", code: "S1", label: "Stopover (Both Cities Same Size)", ratio: 1.0, is_direct: false },
+    Scenario { key: "direct-same", code: "D1", label: "Direct (Both Cities Same Size)", ratio: 1.0, is_direct: true },
+    Scenario { key: "stopover-twice", code: "S2", label: "Stopover (One City Twice as Big)", ratio: 2.0, is_direct: false },
+    Scenario { key: "direct-twice", code: "D2", label: "Direct (One City Twice as Big)", ratio: 2.0, is_direct: true },
+    Scenario { key: "stopover-thrice", code: "S3", label: "Stopover (One City Three or More Times Bigger)", ratio: 3.0, is_direct: false },
+    Scenario { key: "direct-thrice", code: "D3", label: "Direct (One City Three or More Times Bigger)", ratio: 3.0, is_direct: true },
+];
+
+/// Required sum as a line in seats for a fixed scenario: (slope, intercept).
+fn scenario_equation(model: &PolyModel, scenario: &Scenario) -> (f64, f64) {
+    let intercept = model.predict(0.0, scenario.ratio, scenario.is_direct);
+    let slope = model.predict(1.0, scenario.ratio, scenario.is_direct) - intercept;
+    (slope, intercept)
+}
+
+fn equations_report(model: &PolyModel) -> String {
+    SCENARIOS
+        .iter()
+        .map(|scenario| {
+            let (slope, intercept) = scenario_equation(model, scenario);
+            format!("{:<16} y = {:>8.4} * seats + {:>9.2}   {}", scenario.key, slope, intercept, scenario.label)
+        })
+        .chain(std::iter::once(route_gap_note(model)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// IsDirect only shifts the required sum by a constant, so direct and stopover
+/// never cross as the ratio changes: one route costs more everywhere.
+fn route_gap_note(model: &PolyModel) -> String {
+    let gap = model.predict(0.0, 1.0, true) - model.predict(0.0, 1.0, false);
+    if gap == 0.0 {
+        "Direct and stopover require the same sum at every ratio.".to_string()
+    } else {
+        let (dearer, cheaper) = if gap > 0.0 { ("Direct", "stopover") } else { ("Stopover", "direct") };
+        format!("{} requires {:.2} more than {} at every ratio; they never break even.", dearer, gap.abs(), cheaper)
+    }
+}
+
+/// One scenario per line, tab-separated: key, ratio, route, label.
+fn scenarios_report() -> String {
+    SCENARIOS
+        .iter()
+        .map(|s| format!("{}\t{}\t{}\t{}", s.key, s.ratio, if s.is_direct { "direct" } else { "stopover" }, s.label))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// How far model `b`'s predictions moved from model `a`'s over every scenario
+/// at 0..=720 seats in steps of 10. Relative differences are in percent of `a`.
+struct ModelDiff {
+    max_abs: f64,
+    mean_abs: f64,
+    max_rel: f64,
+    mean_rel: f64,
+    worst_scenario: &'static str,
+    worst_seats: f64,
+}
+
+fn diff_models(a: &PolyModel, b: &PolyModel) -> ModelDiff {
+    let mut diff = ModelDiff {
+        max_abs: 0.0,
+        mean_abs: 0.0,
+        max_rel: 0.0,
+        mean_rel: 0.0,
+        worst_scenario: SCENARIOS[0].key,
+        worst_seats: 0.0,
+    };
+    let mut points = 0;
+    for scenario in &SCENARIOS {
+        for seats in (0..=720).step_by(10).map(f64::from) {
+            let before = a.predict(seats, scenario.ratio, scenario.is_direct);
+            let abs = (b.predict(seats, scenario.ratio, scenario.is_direct) - before).abs();
+            let rel = abs / before.abs().max(f64::EPSILON) * 100.0;
+            if abs > diff.max_abs {
+                diff.max_abs = abs;
+                diff.worst_scenario = scenario.key;
+                diff.worst_seats = seats;
+            }
+            diff.max_rel = diff.max_rel.max(rel);
+            diff.mean_abs += abs;
+            diff.mean_rel += rel;
+            points += 1;
+        }
+    }
+    diff.mean_abs /= points as f64;
+    diff.mean_rel /= points as f64;
+    diff
+}
+
+impl ModelDiff {
+    fn report(&self) -> String {
+        format!(
+            "max abs diff : {:.4}\nmean abs diff: {:.4}\nmax rel diff : {:.4}%\nmean rel diff: {:.4}%\nlargest shift: {} at {} seats",
+            self.max_abs, self.mean_abs, self.max_rel, self.mean_rel, self.worst_scenario, self.worst_seats
+        )
+    }
+
+    fn json(&self) -> String {
+        format!(
+            "{{\"max_abs\":{},\"mean_abs\":{},\"max_rel_pct\":{},\"mean_rel_pct\":{},\"worst_scenario\":\"{}\",\"worst_seats\":{}}}",
+            self.max_abs, self.mean_abs, self.max_rel, self.mean_rel, self.worst_scenario, self.worst_seats
+        )
     }
 }
 
+// --- Command Line ---
+
+#[derive(Debug, Default, PartialEq)]
+enum Command {
+    #[default]
+    Tui,
+    Validate,
+    Equations,
+    Scenarios,
+    Curve,
+    Diff,
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    command: Command,
+    model_path: Option<String>,
+    granularity: Option<f64>,
+    debug: bool,
+    mask_input: bool,
+    mask_results: bool,
+    bell: bool,
+    no_color: bool,
+    minimal: bool,
+    watch: bool,
+    weight_overrides: Vec<(usize, f64)>,
+    route_direct: Option<bool>,
+    my_points: Option<f64>,
+    from: Option<f64>,
+    to: Option<f64>,
+    step: Option<f64>,
+    diff_a: Option<String>,
+    diff_b: Option<String>,
+    json: bool,
+    scale: Option<f64>,
+    suffix: String,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut opts = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "validate" => opts.command = Command::Validate,
+                "equations" => opts.command = Command::Equations,
+                "scenarios" => opts.command = Command::Scenarios,
+                "curve" => opts.command = Command::Curve,
+                "diff" => opts.command = Command::Diff,
+                "--a" => opts.diff_a = Some(args.next().ok_or("--a requires a path")?),
+                "--b" => opts.diff_b = Some(args.next().ok_or("--b requires a path")?),
+                "--json" => opts.json = true,
+                "--scale" => {
+                    let scale = parse_number("--scale", args.next())?;
+                    if scale <= 0.0 {
+                        return Err("--scale must be positive".to_string());
+                    }
+                    opts.scale = Some(scale);
+                }
+                "--suffix" => opts.suffix = args.next().ok_or("--suffix requires text")?,
+                "--route" => {
+                    opts.route_direct = match args.next().as_deref() {
+                        Some("direct") => Some(true),
+                        Some("stopover") => Some(false),
+                        _ => return Err("--route must be direct or stopover".to_string()),
+                    };
+                }
+                "--my-points" => opts.my_points = Some(parse_number("--my-points", args.next())?),
+                "--from" => opts.from = Some(parse_number("--from", args.next())?),
+                "--to" => opts.to = Some(parse_number("--to", args.next())?),
+                "--step" => opts.step = Some(parse_number("--step", args.next())?),
+                // CSV is the only export format for now; accepted so scripts can be explicit.
+                "--format" => match args.next().as_deref() {
+                    Some("csv") => {}
+                    Some(other) => return Err(format!("unsupported --format {}; expected csv", other)),
+                    None => return Err("--format requires a value".to_string()),
+                },
+                "--model" => opts.model_path = Some(args.next().ok_or("--model requires a path")?),
+                "--debug" => opts.debug = true,
+                "--mask-input" => opts.mask_input = true,
+                "--mask-results" => opts.mask_results = true,
+                "--bell" => opts.bell = true,
+                "--no-color" => opts.no_color = true,
+                "--minimal" => opts.minimal = true,
+                "--watch" => opts.watch = true,
+                "--set-weight" => {
+                    let spec = args.next().ok_or("--set-weight requires i=value")?;
+                    opts.weight_overrides.push(parse_weight_override(&spec)?);
+                }
+                "--granularity" => {
+                    let n: f64 = args
+                        .next()
+                        .ok_or("--granularity requires a number")?
+                        .parse()
+                        .map_err(|_| "--granularity must be a number")?;
+                    if n <= 0.0 {
+                        return Err("--granularity must be positive".to_string());
+                    }
+                    opts.granularity = Some(n);
+                }
+                other => return Err(format!("unknown argument: {}", other)),
+            }
+        }
+        if opts.command == Command::Validate && opts.model_path.is_none() {
+            return Err("validate requires --model <path>".to_string());
+        }
+        if opts.command == Command::Curve && (opts.route_direct.is_none() || opts.my_points.is_none()) {
+            return Err("curve requires --route and --my-points".to_string());
+        }
+        if opts.command == Command::Diff && (opts.diff_a.is_none() || opts.diff_b.is_none()) {
+            return Err("diff requires --a <model> and --b <model>".to_string());
+        }
+        if opts.watch && matches!(opts.model_path.as_deref(), None | Some("-")) {
+            return Err("--watch requires --model <file>".to_string());
+        }
+        Ok(opts)
+    }
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<f64, String> {
+    value
+        .ok_or_else(|| format!("{} requires a number", flag))?
+        .parse()
+        .map_err(|_| format!("{} must be a number", flag))
+}
+
+/// Parses `i=value`, where `i` is a weight index or feature name (e.g. `Ratio^2=40`).
+fn parse_weight_override(spec: &str) -> Result<(usize, f64), String> {
+    let (target, value) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("--set-weight expects i=value, got {}", spec))?;
+    let index = match target.parse::<usize>() {
+        Ok(index) => index,
+        Err(_) => FEATURE_NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(target))
+            .ok_or_else(|| format!("unknown weight {}; expected 0-{} or one of {:?}", target, FEATURE_NAMES.len() - 1, FEATURE_NAMES))?,
+    };
+    if index >= FEATURE_NAMES.len() {
+        return Err(format!("weight index {} out of range 0-{}", index, FEATURE_NAMES.len() - 1));
+    }
+    let value: f64 = match value.parse::<f64>() {
+        Ok(v) if v.is_finite() => v,
+        _ => return Err(format!("invalid weight value {}", value)),
+    };
+    Ok((index, value))
+}
+
+/// Checks a loaded model before it ships: it must give a finite prediction.
+/// Returns a human-readable report of the weights.
+fn validate_model(model: &PolyModel) -> Result<String, AppError> {
+    let mut report = String::new();
+    for (name, weight) in FEATURE_NAMES.iter().zip(&model.weights) {
+        report.push_str(&format!("{:<10}: {:.3}\n", name, weight));
+    }
+
+    let sanity = model.predict(300.0, 1.0, true);
+    if !sanity.is_finite() {
+        return Err(AppError::InvalidModel(format!("sanity prediction is not finite: {}", sanity)));
+    }
+    report.push_str(&format!("Sanity prediction (300 seats, ratio 1, direct): {:.2}\n", sanity));
+    for scenario in &SCENARIOS {
+        let required = model.predict(300.0, scenario.ratio, scenario.is_direct);
+        report.push_str(&format!("  {:<16} {:>9.2}  {}\n", scenario.key, required, scenario.label));
+    }
+    report.push_str("Model OK");
+    Ok(report)
+}
+
+// --- Model Loading ---
+
+#[derive(Debug)]
+enum AppError {
+    Usage(String),
+    Io { path: String, source: io::Error },
+    InvalidModel(String),
+}
+
+// Process exit codes scripts can rely on. Anything else failing (e.g. the
+// terminal) exits with 1.
+const EXIT_USAGE: i32 = 2;
+const EXIT_MODEL: i32 = 3;
+
+impl AppError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Usage(_) => EXIT_USAGE,
+            AppError::Io { .. } | AppError::InvalidModel(_) => EXIT_MODEL,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Usage(msg) => write!(f, "{}", msg),
+            AppError::Io { path, source } => write!(f, "{}: {}", path, source),
+            AppError::InvalidModel(msg) => write!(f, "invalid model: {}", msg),
+        }
+    }
+}
+
+impl Error for AppError {}
+
+fn exit_code(err: &(dyn Error + 'static)) -> i32 {
+    err.downcast_ref::<AppError>().map_or(1, AppError::exit_code)
+}
+
+/// Deserializes a model and checks it carries one weight per feature.
+/// Shared by the embedded model, --model files and `validate`.
+fn load_model(bytes: &[u8]) -> Result<PolyModel, AppError> {
+    let model: PolyModel = bincode::deserialize(bytes)
+        .map_err(|e| AppError::InvalidModel(format!("cannot deserialize: {}", e)))?;
+    if model.weights.len() != FEATURE_NAMES.len() {
+        return Err(AppError::InvalidModel(format!(
+            "expected {} weights, found {}",
+            FEATURE_NAMES.len(),
+            model.weights.len()
+        )));
+    }
+    if !model.weights.iter().all(|w| w.is_finite()) {
+        return Err(AppError::InvalidModel("model contains non-finite weights".to_string()));
+    }
+    Ok(model)
+}
+
+/// Writes `model` in the same bincode format model_builder produces.
+fn save_model(model: &PolyModel, path: &str) -> Result<(), AppError> {
+    if !model.weights.iter().all(|w| w.is_finite()) {
+        return Err(AppError::InvalidModel("model contains non-finite weights".to_string()));
+    }
+    let bytes = bincode::serialize(model).map_err(|e| AppError::InvalidModel(format!("cannot serialize: {}", e)))?;
+    std::fs::write(path, bytes).map_err(|source| AppError::Io { path: path.to_string(), source })
+}
+
+/// Reads a serialized model from `path`, or from stdin when `path` is `-`.
+fn read_model_bytes(path: &str) -> io::Result<Vec<u8>> {
+    if path == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        std::fs::read(path)
+    }
+}
+
+/// Loads a model from `path`, or from stdin when `path` is `-`.
+fn load_model_from_path(path: &str) -> Result<PolyModel, AppError> {
+    let bytes = read_model_bytes(path).map_err(|source| AppError::Io { path: path.to_string(), source })?;
+    load_model(&bytes)
+}
+
 // --- App Logic ---
 
+/// City-size ratio as the model sees it: larger over smaller, with points floored at 1.
+fn city_ratio(p1: f64, p2: f64) -> f64 {
+    p1.max(p2) / p1.min(p2).max(1.0)
+}
+
+/// Points both cities must add up to, given each city's points.
+fn required_sum(model: &PolyModel, my_points: f64, other_points: f64, seats: f64, is_direct: bool) -> f64 {
+    model.predict(seats, city_ratio(my_points, other_points), is_direct)
+}
+
+/// Least points the other city needs so both cities reach the required sum.
+/// The required sum itself depends on the other city through the ratio, so this
+/// bisects over 0..50,000; `None` if even that is not enough.
+fn other_city_needed(model: &PolyModel, seats: f64, my_points: f64, is_direct: bool) -> Option<f64> {
+    let p1 = my_points;
+    let mut low = 0.0;
+    let mut high = 50_000.0;
+    let mut ans = -1.0;
+
+    for _ in 0..60 { 
+        let mid = (low + high) / 2.0;
+        let p2 = mid;
+        
+        let req_sum = required_sum(model, p1, p2, seats, is_direct);
+        
+        if p1 + p2 >= req_sum {
+            ans = p2;
+            high = mid; 
+        } else {
+            low = mid; 
+        }
+    }
+    
+    if ans < 0.0 { None } else { Some(ans) }
+}
+
+// Keeps a tiny --step from bisecting millions of rows.
+const MAX_CURVE_ROWS: u64 = 100_000;
+
+/// CSV of seats, required sum and other-city-needed from `from` to `to` seats.
+/// Cells are left empty where the solver finds no answer.
+fn curve_csv(model: &PolyModel, is_direct: bool, my_points: f64, from: f64, to: f64, step: f64) -> Result<String, String> {
+    if !(step > 0.0 && step.is_finite()) {
+        return Err("--step must be positive".to_string());
+    }
+    if !(0.0 <= from && from <= to && to.is_finite()) {
+        return Err("--from and --to must satisfy 0 <= from <= to".to_string());
+    }
+
+    let steps = ((to - from) / step + 1e-9).floor() as u64;
+    if steps >= MAX_CURVE_ROWS {
+        return Err(format!("--step is too small: the range would need more than {} rows", MAX_CURVE_ROWS));
+    }
+
+    let mut csv = String::from("seats,required_sum,other_city_needed\n");
+    for i in 0..=steps {
+        let seats = from + i as f64 * step;
+        match other_city_needed(model, seats, my_points, is_direct) {
+            Some(other) => {
+                let required = required_sum(model, my_points, other, seats, is_direct);
+                csv.push_str(&format!("{},{:.2},{:.2}\n", seats, required, other));
+            }
+            None => csv.push_str(&format!("{},,\n", seats)),
+        }
+    }
+    Ok(csv)
+}
+
+/// Percentages of `required` covered by my city and left to the other city;
+/// `None` unless the required sum is positive.
+fn split_percent(my_points: f64, required: f64) -> Option<(f64, f64)> {
+    if required <= 0.0 {
+        return None;
+    }
+    let mine = (my_points / required * 100.0).clamp(0.0, 100.0);
+    Some((mine, 100.0 - mine))
+}
+
+/// Required sum spread over the plane's seats; `None` for a zero-seat plane.
+fn points_per_seat(required_sum: f64, seats: f64) -> Option<f64> {
+    (seats > 0.0).then_some(required_sum / seats)
+}
+
+/// Hides digits for screen recordings while keeping the field's width.
+fn masked(text: String, mask: bool) -> String {
+    if mask { "*".repeat(text.chars().count()) } else { text }
+}
+
+/// Red while the other city must contribute, deeper the larger its share of the
+/// total; green once my city covers the requirement alone.
+fn needed_color(needed: f64, my_points: f64) -> Color {
+    if needed <= 0.0 {
+        return Color::Green;
+    }
+    let share = needed / (needed + my_points.max(0.0));
+    Color::Rgb((140.0 + 115.0 * share).round() as u8, 0, 0)
+}
+
+/// evalexpr has no unary plus, so drop one leading `+` the way `-` is already accepted there.
+fn strip_leading_plus(input: &str) -> &str {
+    let trimmed = input.trim_start();
+    trimmed.strip_prefix('+').unwrap_or(trimmed)
+}
+
+/// Rounds to the nearest multiple of `granularity`, matching the steps city points move in.
+fn round_to_granularity(value: f64, granularity: f64) -> f64 {
+    (value / granularity).round() * granularity
+}
+
+/// Maps full-width digits (U+FF10..=U+FF19) to ASCII so CJK input methods work.
+/// Any other non-ASCII character is unsupported and yields `None`.
+fn normalize_input_char(c: char) -> Option<char> {
+    match c {
+        '\u{FF10}'..='\u{FF19}' => char::from_digit(c as u32 - 0xFF10, 10),
+        c if c.is_ascii() => Some(c),
+        _ => None,
+    }
+}
+
+// Longest expression accepted in the input field; keeps it inside its block.
+const MAX_INPUT_LEN: usize = 24;
+
+// How long a rejected keystroke keeps the input field inverted.
+const FLASH_DURATION: Duration = Duration::from_millis(120);
+
+// How often --watch checks the model file's modification time.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+struct ModelWatch {
+    path: String,
+    loaded: Option<SystemTime>,
+    pending: Option<SystemTime>,
+}
+
+// Number of recent Direct Needs values kept for the sparkline.
+const HISTORY_LEN: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Notation {
+    Fixed,
+    Scientific,
+}
+
 enum InputMode {
     Normal,
     Editing,
+    // Typing the path for Ctrl+S
+    Saving,
+}
+
+impl InputMode {
+    fn label(&self) -> &'static str {
+        match self {
+            InputMode::Normal => " NORMAL ",
+            InputMode::Editing => " EDITING ",
+            InputMode::Saving => " SAVING ",
+        }
+    }
 }
 
 struct App {
     // Inputs
     my_city_input: String,
+    save_path_input: String,
     
     // State
     input_mode: InputMode,
+    // Active model: base_model with any --set-weight overrides applied
     model: PolyModel,
+    base_model: PolyModel,
+    embedded_model: PolyModel,
+    overrides: Vec<(usize, f64)>,
+    
+    granularity: Option<f64>,
+    // Results are shown divided by `scale` and followed by `suffix`, e.g. 1000 and "k".
+    scale: f64,
+    suffix: String,
+    notation: Notation,
+    debug: bool,
+    compute_time: Option<Duration>,
+    mask_input: bool,
+    mask_results: bool,
+    bell: bool,
+    color: bool,
+    minimal: bool,
+    flash: bool,
     
     // Calculated
     p1_value: Option<f64>, 
+    notice: Option<String>,
     
     // Chart Data
     chart_x_cursor: f64, 
@@ -54,27 +611,166 @@ struct App {
     data_direct: Vec<(f64, f64)> ,
     y_min: f64,
     y_max: f64,
+    needed_history: VecDeque<u64>,
+    show_legend: bool,
+    show_coefficients: bool,
+    show_scenario_bars: bool,
+    error_popup: Option<String>,
+    watch: Option<ModelWatch>,
 }
 
 impl App {
-    fn new(model: PolyModel) -> App {
+    fn new(embedded_model: PolyModel, model: PolyModel) -> App {
         let mut app = App {
             my_city_input: String::new(),
+            save_path_input: String::new(),
             input_mode: InputMode::Normal,
+            base_model: model.clone(),
             model,
+            embedded_model,
+            overrides: Vec::new(),
+            granularity: None,
+            scale: 1.0,
+            suffix: String::new(),
+            notation: Notation::Fixed,
+            debug: false,
+            compute_time: None,
+            mask_input: false,
+            mask_results: false,
+            bell: false,
+            color: true,
+            minimal: false,
+            flash: false,
             p1_value: None,
+            notice: None,
             chart_x_cursor: 300.0, 
             data_stopover: vec![],
             data_direct: vec![],
             y_min: 0.0,
             y_max: 2000.0,
+            needed_history: VecDeque::with_capacity(HISTORY_LEN),
+            show_legend: false,
+            show_coefficients: false,
+            show_scenario_bars: false,
+            error_popup: None,
+            watch: None,
         };
         app.update_calculation();
         app
     }
 
+    fn insert_char(&mut self, c: char) {
+        if self.my_city_input.chars().count() >= MAX_INPUT_LEN {
+            self.reject(format!("limit of {} characters reached", MAX_INPUT_LEN));
+            return;
+        }
+        match normalize_input_char(c) {
+            Some(c) => self.my_city_input.push(c),
+            None => self.reject("unsupported character".to_string()),
+        }
+    }
+
+    /// Feedback for a dropped keystroke: a note, a one-frame flash and, with --bell, a beep.
+    fn reject(&mut self, notice: String) {
+        self.notice = Some(notice);
+        self.flash = true;
+        if self.bell {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
+    }
+
+    fn revert_model(&mut self) {
+        self.base_model = self.embedded_model.clone();
+        self.apply_overrides();
+        self.notice = Some("Reverted to built-in model.".to_string());
+    }
+
+    /// Reloads the watched model file once its mtime has changed and then held still
+    /// for one poll, so a file model_builder is still writing is not picked up.
+    /// A failed read keeps the current model; the writer's next save retries.
+    fn poll_model_file(&mut self) {
+        let Some(watch) = &mut self.watch else { return };
+        let modified = std::fs::metadata(&watch.path).and_then(|m| m.modified()).ok();
+        if modified == watch.loaded {
+            watch.pending = None;
+            return;
+        }
+        if watch.pending != modified {
+            watch.pending = modified;
+            return;
+        }
+        watch.loaded = modified;
+        watch.pending = None;
+
+        let path = watch.path.clone();
+        match load_model_from_path(&path) {
+            Ok(model) => {
+                self.base_model = model;
+                self.apply_overrides();
+                self.notice = Some("Model reloaded.".to_string());
+            }
+            Err(err) => self.notice = Some(format!("Reload failed: {}", err)),
+        }
+    }
+
+    /// Back to the built-in model with no weight overrides, in one step.
+    fn reset_state(&mut self) {
+        self.base_model = self.embedded_model.clone();
+        self.set_overrides(Vec::new());
+        self.notice = Some("State reset.".to_string());
+    }
+
+    fn set_overrides(&mut self, overrides: Vec<(usize, f64)>) {
+        self.overrides = overrides;
+        self.apply_overrides();
+    }
+
+    fn clear_overrides(&mut self) {
+        self.set_overrides(Vec::new());
+        self.notice = Some("Cleared weight overrides.".to_string());
+    }
+
+    fn apply_overrides(&mut self) {
+        let mut model = self.base_model.clone();
+        for &(index, value) in &self.overrides {
+            if let Some(weight) = model.weights.get_mut(index) {
+                *weight = value;
+            }
+        }
+        self.model = model;
+        self.update_calculation();
+    }
+
+    fn start_save(&mut self) {
+        if self.save_path_input.is_empty() {
+            self.save_path_input = "model.bin".to_string();
+        }
+        self.input_mode = InputMode::Saving;
+    }
+
+    fn finish_save(&mut self) {
+        self.input_mode = InputMode::Normal;
+        match save_model(&self.model, &self.save_path_input) {
+            Ok(()) => self.notice = Some(format!("Saved model to {}", self.save_path_input)),
+            Err(err) => self.error_popup = Some(err.to_string()),
+        }
+    }
+
+    fn is_modified(&self) -> bool {
+        !self.overrides.is_empty()
+    }
+
     fn update_calculation(&mut self) {
-        match eval(&self.my_city_input) {
+        let started = self.debug.then(Instant::now);
+        self.recalculate();
+        if let Some(started) = started {
+            self.compute_time = Some(started.elapsed());
+        }
+    }
+
+    fn recalculate(&mut self) {
+        match eval(strip_leading_plus(&self.my_city_input)) {
             Ok(val) => match val.as_float() {
                 Ok(f) => self.p1_value = Some(f),
                 Err(_) => self.p1_value = val.as_int().ok().map(|i| i as f64),
@@ -122,34 +818,155 @@ impl App {
         if min_y == f64::MAX { min_y = 0.0; max_y = 1000.0; }
         self.y_min = (min_y - 100.0).max(0.0);
         self.y_max = max_y + 100.0;
+
+        self.record_history();
+    }
+
+    fn move_cursor(&mut self, delta: f64) {
+        self.chart_x_cursor = (self.chart_x_cursor + delta).clamp(0.0, 720.0);
+        self.record_history();
+    }
+
+    fn record_history(&mut self) {
+        if let (_, Some(direct)) = self.get_values_at_cursor() {
+            if self.needed_history.len() == HISTORY_LEN {
+                self.needed_history.pop_front();
+            }
+            self.needed_history.push_back(direct.max(0.0).round() as u64);
+        }
     }
 
     fn solve_p2(&self, p1: f64, seats: f64, is_direct: bool) -> Option<f64> {
-        let mut low = 0.0;
-        let mut high = 50_000.0;
-        let mut ans = -1.0;
+        other_city_needed(&self.model, seats, p1, is_direct)
+    }
+    
+    fn required_sum(&self, p1: f64, p2: f64, seats: f64, is_direct: bool) -> f64 {
+        required_sum(&self.model, p1, p2, seats, is_direct)
+    }
 
-        for _ in 0..60 { 
-            let mid = (low + high) / 2.0;
-            let p2 = mid;
-            
-            let min_p = p1.min(p2).max(1.0); 
-            let max_p = p1.max(p2);
-            let ratio = max_p / min_p;
-            
-            let req_sum = self.model.predict(seats, ratio, is_direct);
-            
-            if p1 + p2 >= req_sum {
-                ans = p2;
-                high = mid; 
-            } else {
-                low = mid; 
+    /// `style` with its colors dropped under --no-color / NO_COLOR; modifiers are kept.
+    fn paint(&self, style: Style) -> Style {
+        if self.color { style } else { Style { fg: None, bg: None, ..style } }
+    }
+
+    fn display_value(&self, value: f64) -> f64 {
+        match self.granularity {
+            Some(n) => round_to_granularity(value, n),
+            None => value,
+        }
+    }
+
+    fn format_value(&self, value: f64) -> String {
+        masked(self.notation_text(self.display_value(value) / self.scale), self.mask_results) + &self.suffix
+    }
+
+    /// For points per seat: a ratio, so neither granularity nor --scale applies.
+    fn format_per_seat(&self, value: f64) -> String {
+        masked(self.notation_text(value), self.mask_results)
+    }
+
+    fn notation_text(&self, value: f64) -> String {
+        match self.notation {
+            Notation::Fixed => format!("{:.2}", value),
+            Notation::Scientific => format!("{:.3e}", value),
+        }
+    }
+
+    /// Compact form for axis and gauge labels: whole points, or two decimals once scaled.
+    fn short_value(&self, value: f64, mask: bool) -> String {
+        let value = value / self.scale;
+        let text = if self.scale == 1.0 { format!("{:.0}", value) } else { format!("{:.2}", value) };
+        masked(text, mask) + &self.suffix
+    }
+
+    fn toggle_notation(&mut self) {
+        self.notation = match self.notation {
+            Notation::Fixed => Notation::Scientific,
+            Notation::Scientific => Notation::Fixed,
+        };
+    }
+
+    /// Applies one terminal event; returns `true` when the app should quit.
+    /// Resizes need no state change: the loop redraws after every event.
+    fn handle_event(&mut self, event: Event) -> bool {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(key),
+            Event::Paste(text) => {
+                if let InputMode::Editing = self.input_mode {
+                    self.notice = None;
+                    for c in text.chars().filter(|c| !c.is_control()) {
+                        self.insert_char(c);
+                    }
+                }
+                false
             }
+            _ => false,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.notice = None;
+
+        // Ctrl+C quits from any mode through the same terminal cleanup as 'q'.
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return true;
+        }
+
+        // Any other key dismisses an error popup.
+        if self.error_popup.take().is_some() {
+            return false;
         }
         
-        if ans < 0.0 { None } else { Some(ans) }
+        match self.input_mode {
+            InputMode::Normal => match key.code {
+                KeyCode::Char('q') => return true,
+                KeyCode::Enter | KeyCode::Char('i') => self.input_mode = InputMode::Editing,
+                KeyCode::F(2) => self.revert_model(),
+                KeyCode::Char('l') => self.show_legend = !self.show_legend,
+                KeyCode::Char('e') => self.toggle_notation(),
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => self.start_save(),
+                KeyCode::Char('c') => self.show_coefficients = !self.show_coefficients,
+                KeyCode::Char('b') => self.show_scenario_bars = !self.show_scenario_bars,
+                KeyCode::Char('o') => self.clear_overrides(),
+                KeyCode::Char('r') => self.reset_state(),
+                KeyCode::Esc => {
+                    self.show_legend = false;
+                    self.show_coefficients = false;
+                    self.show_scenario_bars = false;
+                }
+                KeyCode::Left => self.move_cursor(-10.0),
+                KeyCode::Right => self.move_cursor(10.0),
+                _ => {} 
+            },
+            InputMode::Editing => match key.code {
+                KeyCode::Enter => {
+                    self.input_mode = InputMode::Normal;
+                    self.update_calculation();
+                },
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                },
+                KeyCode::Char(c) => {
+                    self.insert_char(c);
+                },
+                KeyCode::Backspace => {
+                    self.my_city_input.pop();
+                },
+                _ => {} 
+            },
+            InputMode::Saving => match key.code {
+                KeyCode::Enter => self.finish_save(),
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Char(c) => self.save_path_input.push(c),
+                KeyCode::Backspace => {
+                    self.save_path_input.pop();
+                },
+                _ => {}
+            }
+        }
+        false
     }
-    
+
     fn get_values_at_cursor(&self) -> (Option<f64>, Option<f64>) {
         if let Some(p1) = self.p1_value {
             let v1 = self.solve_p2(p1, self.chart_x_cursor, false);
@@ -161,103 +978,240 @@ impl App {
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let model_data = include_bytes!("../model.bin");
-    let model: PolyModel = bincode::deserialize(model_data)?;
+fn main() {
+    let result = Options::parse(std::env::args().skip(1))
+        .map_err(|msg| AppError::Usage(msg).into())
+        .and_then(run);
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        std::process::exit(exit_code(err.as_ref()));
+    }
+}
+
+fn run(opts: Options) -> Result<(), Box<dyn Error>> {
+    if let (Command::Validate, Some(path)) = (&opts.command, &opts.model_path) {
+        println!("{}", load_model_from_path(path).and_then(|model| validate_model(&model))?);
+        return Ok(());
+    }
+
+    if opts.command == Command::Scenarios {
+        println!("{}", scenarios_report());
+        return Ok(());
+    }
+
+    if let (Command::Diff, Some(a), Some(b)) = (&opts.command, &opts.diff_a, &opts.diff_b) {
+        let diff = diff_models(&load_model_from_path(a)?, &load_model_from_path(b)?);
+        println!("{}", if opts.json { diff.json() } else { diff.report() });
+        return Ok(());
+    }
+
+    // Set by build.rs from ATO3_MODEL_PATH, defaulting to ../model.bin.
+    let model_data = include_bytes!(env!("ATO3_EMBEDDED_MODEL"));
+    let embedded_model = load_model(model_data)?;
+    let model = match &opts.model_path {
+        Some(path) => load_model_from_path(path)?,
+        None => embedded_model.clone(),
+    };
+    if model.is_trivial() {
+        eprintln!("WARNING: every model weight is zero; all predictions will be 0.");
+    }
+
+    if opts.command == Command::Equations {
+        println!("{}", equations_report(&model));
+        return Ok(());
+    }
+
+    if let (Command::Curve, Some(is_direct), Some(my_points)) = (&opts.command, opts.route_direct, opts.my_points) {
+        let csv = curve_csv(
+            &model,
+            is_direct,
+            my_points,
+            opts.from.unwrap_or(0.0),
+            opts.to.unwrap_or(720.0),
+            opts.step.unwrap_or(10.0),
+        )
+        .map_err(AppError::Usage)?;
+        print!("{}", csv);
+        return Ok(());
+    }
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new(model);
+    let mut app = App::new(embedded_model, model);
+    app.granularity = opts.granularity;
+    app.scale = opts.scale.unwrap_or(1.0);
+    app.suffix = opts.suffix;
+    app.debug = opts.debug;
+    app.mask_input = opts.mask_input;
+    app.mask_results = opts.mask_results;
+    app.bell = opts.bell;
+    app.color = !opts.no_color && std::env::var_os("NO_COLOR").is_none();
+    app.minimal = opts.minimal;
+    app.set_overrides(opts.weight_overrides);
+    if app.model.is_trivial() {
+        app.notice = Some("Warning: every model weight is zero.".to_string());
+    }
+    if let (true, Some(path)) = (opts.watch, &opts.model_path) {
+        let loaded = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        app.watch = Some(ModelWatch { path: path.clone(), loaded, pending: None });
+    }
     let res = run_app(&mut terminal, app);
 
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        println!("{:?}", err)
-    }
-
-    Ok(())
+    Ok(res?)
 }
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
     loop {
-        terminal.draw(|f| ui(f, &app))?;
+        terminal.draw(|f| if app.minimal { ui_minimal(f, &app) } else { ui(f, &app) })?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind != KeyEventKind::Press { continue; }
-            
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Enter => app.input_mode = InputMode::Editing,
-                    KeyCode::Left => {
-                        app.chart_x_cursor = (app.chart_x_cursor - 10.0).max(0.0);
-                    },
-                    KeyCode::Right => {
-                        app.chart_x_cursor = (app.chart_x_cursor + 10.0).min(720.0);
-                    },
-                    _ => {} 
-                },
-                InputMode::Editing => match key.code {
-                    KeyCode::Enter => {
-                        app.input_mode = InputMode::Normal;
-                        app.update_calculation();
-                    },
-                    KeyCode::Esc => {
-                        app.input_mode = InputMode::Normal;
-                    },
-                    KeyCode::Char(c) => {
-                        app.my_city_input.push(c);
-                    },
-                    KeyCode::Backspace => {
-                        app.my_city_input.pop();
-                    },
-                    _ => {} 
-                }
+        // Keep the flash frame up briefly, then redraw without it unless a key arrives first.
+        if app.flash {
+            app.flash = false;
+            if !event::poll(FLASH_DURATION)? {
+                continue;
             }
         }
+
+        if app.watch.is_some() && !event::poll(WATCH_INTERVAL)? {
+            app.poll_model_file();
+            continue;
+        }
+
+        if app.handle_event(event::read()?) {
+            return Ok(());
+        }
     }
 }
 
-fn ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
+// Terminals at least this wide put the chart beside the other panels.
+const WIDE_LAYOUT_MIN_WIDTH: u16 = 140;
+
+struct Areas {
+    input: Rect,
+    chart: Rect,
+    split: Rect,
+    footer: Rect,
+}
+
+fn layout_areas(area: Rect) -> Areas {
+    if area.width < WIDE_LAYOUT_MIN_WIDTH {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints(
+                [
+                    Constraint::Length(3), // Input
+                    Constraint::Min(10),   // Chart
+                    Constraint::Length(3), // Split
+                    Constraint::Length(5), // Info
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        return Areas { input: chunks[0], chart: chunks[1], split: chunks[2], footer: chunks[3] };
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
         .margin(2)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(area);
+    let left = Layout::default()
+        .direction(Direction::Vertical)
         .constraints(
             [
                 Constraint::Length(3), // Input
-                Constraint::Min(10),   // Chart
+                Constraint::Length(3), // Split
                 Constraint::Length(5), // Info
+                Constraint::Min(0),
             ]
             .as_ref(),
         )
-        .split(f.size());
+        .split(columns[0]);
+    Areas { input: left[0], chart: columns[1], split: left[1], footer: left[2] }
+}
+
+/// --minimal: plain unstyled lines with no borders, chart or popups, so each
+/// redraw sends only the few cells that changed.
+fn ui_minimal(f: &mut Frame, app: &App) {
+    let input = match app.input_mode {
+        InputMode::Saving => format!("Save to: {}", app.save_path_input),
+        _ => format!("My City Points: {}", masked(app.my_city_input.clone(), app.mask_input)),
+    };
+    let (stop_val, dir_val) = app.get_values_at_cursor();
+    let mut lines = vec![
+        format!("{} {}", input, app.input_mode.label()),
+        format!("Seats: {:.0} (Left/Right)", app.chart_x_cursor),
+        format!(
+            "Stopover Needs: {} | Direct Needs: {}",
+            app.format_value(stop_val.unwrap_or(0.0)),
+            app.format_value(dir_val.unwrap_or(0.0))
+        ),
+    ];
+    if let Some(message) = app.error_popup.as_ref().or(app.notice.as_ref()) {
+        lines.push(message.clone());
+    }
+    f.render_widget(Paragraph::new(lines.join("\n")), f.size());
+}
+
+fn ui(f: &mut Frame, app: &App) {
+    let areas = layout_areas(f.size());
 
     // --- Input Area ---
-    let input_style = match app.input_mode {
-        InputMode::Editing => Style::default().fg(Color::Yellow),
-        InputMode::Normal => Style::default().fg(Color::Green),
+    let mut input_style = match app.input_mode {
+        InputMode::Editing => app.paint(Style::default().fg(Color::Yellow)),
+        InputMode::Normal => app.paint(Style::default().fg(Color::Green)),
+        InputMode::Saving => app.paint(Style::default().fg(Color::Magenta)),
     };
+    if app.flash {
+        input_style = input_style.add_modifier(Modifier::REVERSED);
+    }
     
-    let input_block = Block::default()
+    let (input_title, input_value) = match app.input_mode {
+        InputMode::Saving => ("Save Model To (Enter to Save, Esc to Cancel)", app.save_path_input.clone()),
+        InputMode::Normal => (
+            "My City Points (Enter or i to Edit, Math Allowed: e.g. 100+200)",
+            masked(app.my_city_input.clone(), app.mask_input),
+        ),
+        InputMode::Editing => (
+            "My City Points (Math Allowed: e.g. 100+200)",
+            masked(app.my_city_input.clone(), app.mask_input),
+        ),
+    };
+    let mode = Span::styled(
+        app.input_mode.label(),
+        app.paint(Style::default().fg(Color::Black).bg(input_style.fg.unwrap_or(Color::White)).add_modifier(Modifier::BOLD)),
+    );
+    let mut input_block = Block::default()
         .borders(Borders::ALL)
-        .title("My City Points (Math Allowed: e.g. 100+200)");
+        .title(input_title)
+        .title(block::Title::from(mode).alignment(Alignment::Right));
+    if let Some(notice) = &app.notice {
+        input_block = input_block.title(
+            block::Title::from(notice.as_str())
+                .position(block::Position::Bottom)
+                .alignment(Alignment::Right),
+        );
+    }
         
-    let input_text = Paragraph::new(app.my_city_input.as_str())
+    let input_text = Paragraph::new(input_value)
         .style(input_style)
         .block(input_block);
         
-    f.render_widget(input_text, chunks[0]);
+    f.render_widget(input_text, areas.input);
 
     // --- Chart Area ---
     if app.p1_value.is_some() {
@@ -287,9 +1241,9 @@ fn ui(f: &mut Frame, app: &App) {
         let y_min = app.y_min;
         let y_max = app.y_max;
         let y_labels = vec![
-            Span::raw(format!("{:.0}", y_min)),
-            Span::raw(format!("{:.0}", (y_min+y_max)/2.0)),
-            Span::raw(format!("{:.0}", y_max)),
+            Span::raw(app.short_value(y_min, app.mask_results)),
+            Span::raw(app.short_value((y_min+y_max)/2.0, app.mask_results)),
+            Span::raw(app.short_value(y_max, app.mask_results)),
         ];
 
         // Cursor Line Dataset
@@ -302,73 +1256,820 @@ fn ui(f: &mut Frame, app: &App) {
             Dataset::default()
                 .name("Stopover")
                 .marker(symbols::Marker::Braille)
-                .style(Style::default().fg(Color::Red))
+                .style(app.paint(Style::default().fg(Color::Red)))
                 .graph_type(GraphType::Line)
                 .data(&app.data_stopover),
             Dataset::default()
                 .name("Direct")
                 .marker(symbols::Marker::Braille)
-                .style(Style::default().fg(Color::Cyan))
+                .style(app.paint(Style::default().fg(Color::Cyan)))
                 .graph_type(GraphType::Line)
                 .data(&app.data_direct),
             Dataset::default()
                 .name("Selected")
                 .marker(symbols::Marker::Braille)
-                .style(Style::default().fg(Color::Yellow))
+                .style(app.paint(Style::default().fg(Color::Yellow)))
                 .graph_type(GraphType::Line)
                 .data(&cursor_data),
         ];
 
+        let mut chart_title = "Other City Needed (Y) vs Plane Seats (X) - [Use Left/Right to Scroll]".to_string();
+        if app.is_modified() {
+            chart_title.push_str(" [MODIFIED MODEL]");
+        }
+
         let chart = Chart::new(datasets)
-            .block(Block::default().title("Other City Needed (Y) vs Plane Seats (X) - [Use Left/Right to Scroll]").borders(Borders::ALL))
+            .block(Block::default().title(chart_title).borders(Borders::ALL))
             .x_axis(
                 Axis::default()
                     .title("Seats")
-                    .style(Style::default().fg(Color::Gray))
+                    .style(app.paint(Style::default().fg(Color::Gray)))
                     .bounds([x_min, x_max])
                     .labels(x_labels),
             )
             .y_axis(
                 Axis::default()
                     .title("Other City Points")
-                    .style(Style::default().fg(Color::Gray))
+                    .style(app.paint(Style::default().fg(Color::Gray)))
                     .bounds([y_min, y_max])
                     .labels(y_labels),
             );
             
-        f.render_widget(chart, chunks[1]);
+        f.render_widget(chart, areas.chart);
         
     } else {
         let warning = Paragraph::new("Please enter a valid number or expression (e.g. '100+50') and press Enter.")
-            .style(Style::default().fg(Color::Red))
+            .style(app.paint(Style::default().fg(Color::Red)))
             .block(Block::default().borders(Borders::ALL));
-        f.render_widget(warning, chunks[1]);
+        f.render_widget(warning, areas.chart);
     }
 
-    // --- Info / Cursor Area ---
+    // --- Split Area ---
     let (stop_val, dir_val) = app.get_values_at_cursor();
+
+    if let Some(p1) = app.p1_value {
+        let halves = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(areas.split);
+        f.render_widget(contribution_gauge("Stopover Split", app, p1, stop_val), halves[0]);
+        f.render_widget(contribution_gauge("Direct Split", app, p1, dir_val), halves[1]);
+    }
+
+    // --- Info / Cursor Area ---
     
-    let info_text = format!(
-        "Selected Plane Size: {:.0} Seats\nStopover Needs: {:.2} | Direct Needs: {:.2}",
-        app.chart_x_cursor,
-        stop_val.unwrap_or(0.0),
-        dir_val.unwrap_or(0.0)
-    );
+    let per_seat = |needed: Option<f64>, is_direct: bool| match (app.p1_value, needed) {
+        (Some(p1), Some(p2)) => {
+            let required = app.required_sum(p1, p2, app.chart_x_cursor, is_direct);
+            points_per_seat(required, app.chart_x_cursor).map(|v| app.format_per_seat(v))
+        }
+        _ => None,
+    }
+    .unwrap_or_else(|| "n/a".to_string());
+
+    let needs = |needed: Option<f64>| {
+        let text = app.format_value(needed.unwrap_or(0.0));
+        match (app.p1_value, needed) {
+            (Some(p1), Some(needed)) if app.color => Span::styled(text, Style::default().fg(needed_color(needed, p1))),
+            _ => Span::raw(text),
+        }
+    };
+    let info_text = vec![
+        Line::from(format!("Selected Plane Size: {:.0} Seats", app.chart_x_cursor)),
+        Line::from(vec![
+            Span::raw("Stopover Needs: "),
+            needs(stop_val),
+            Span::raw(" | Direct Needs: "),
+            needs(dir_val),
+        ]),
+        Line::from(format!("Per Seat: Stopover {} | Direct {}", per_seat(stop_val, false), per_seat(dir_val, true))),
+    ];
     
+    let info_title = match app.compute_time {
+        Some(elapsed) => format!("Precise Prediction [compute {:.2?}]", elapsed),
+        None => "Precise Prediction".to_string(),
+    };
     let info_block = Paragraph::new(info_text)
-        .block(Block::default().borders(Borders::ALL).title("Precise Prediction"))
-        .style(Style::default().fg(Color::White).bg(Color::Black));
+        .block(Block::default().borders(Borders::ALL).title(info_title))
+        .style(app.paint(Style::default().fg(Color::White).bg(Color::Black)));
         
-    f.render_widget(info_block, chunks[2]);
+    let footer = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(areas.footer);
+    f.render_widget(info_block, footer[0]);
+
+    let history: Vec<u64> = app.needed_history.iter().copied().collect();
+    let sparkline = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title("Direct Needs History"))
+        .style(app.paint(Style::default().fg(Color::Cyan)))
+        .data(&history);
+    f.render_widget(sparkline, footer[1]);
+
+    if app.show_legend {
+        let area = centered_rect(70, 60, f.size());
+        let legend = Paragraph::new(LEGEND)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("Scenario Legend - [l/Esc to Close]"));
+        f.render_widget(Clear, area);
+        f.render_widget(legend, area);
+    }
+
+    if app.show_coefficients {
+        let lines: Vec<Line> = FEATURE_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let original = app.base_model.weights.get(i).copied().unwrap_or(0.0);
+                let active = app.model.weights.get(i).copied().unwrap_or(0.0);
+                if app.overrides.iter().any(|&(index, _)| index == i) {
+                    Line::from(format!("{:<10}: {:.4} -> {:.4} (override)", name, original, active))
+                } else {
+                    Line::from(format!("{:<10}: {:.4}", name, original))
+                }
+            })
+            .collect();
+        let title = if app.is_modified() { "Coefficients [MODIFIED] - [o to Clear, c/Esc to Close]" } else { "Coefficients - [c/Esc to Close]" };
+        let area = centered_rect(60, 40, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+
+    if app.show_scenario_bars {
+        let area = centered_rect(60, 50, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(scenario_bar_chart(app), area);
+    }
+
+    if let Some(error) = &app.error_popup {
+        let area = centered_rect(60, 20, f.size());
+        let popup = Paragraph::new(error.as_str())
+            .wrap(Wrap { trim: false })
+            .style(app.paint(Style::default().fg(Color::Red)))
+            .block(Block::default().borders(Borders::ALL).title("Error - [Any Key to Close]"));
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+}
+
+const LEGEND: &str = "\
+Ratio: larger city's points / smaller city's points. The solver derives it
+from both cities, so it is never entered by hand.
+  Same Size                  -> ratio 1
+  One City Twice as Big      -> ratio 2
+  Three or More Times Bigger -> ratio 3 and above
+
+Direct: the route flies between the two cities with no stopover.
+Stopover: the route lands at an intermediate city on the way.
+
+Required sum: points both cities must add up to for the plane size.
+Other City Needed: the least the other city must have so that
+My City + Other City reaches the required sum.";
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Bar of my city's share of the required sum; the rest is what the other city must bring.
+fn contribution_gauge<'a>(title: &'a str, app: &App, my_points: f64, needed: Option<f64>) -> Gauge<'a> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let gauge = Gauge::default()
+        .block(block)
+        .gauge_style(app.paint(Style::default().fg(Color::Green).bg(Color::Red)));
+    let mine = app.short_value(my_points, app.mask_input);
+
+    match needed {
+        // The solver never goes below zero, so a vanishing need means my city covers it all.
+        Some(other) if other < 0.01 => gauge
+            .ratio(1.0)
+            .label(format!("My City {} | surplus", mine)),
+        Some(other) => {
+            // The solver's least answer makes my + other the required sum.
+            let total = my_points + other;
+            let (mine_pct, other_pct) = split_percent(my_points, total).unwrap_or((0.0, 0.0));
+            gauge.ratio(mine_pct / 100.0).label(format!(
+                "My City {} ({:.0}%) + Other {} ({:.0}%) = {}",
+                mine,
+                mine_pct,
+                app.short_value(app.display_value(other), app.mask_results),
+                other_pct,
+                app.short_value(app.display_value(total), app.mask_results)
+            ))
+        }
+        None => gauge.ratio(0.0).label("no solution"),
+    }
+}
+
+/// One bar per scenario: the required sum at the cursor's seat count.
+fn scenario_bar_chart(app: &App) -> BarChart<'static> {
+    let sums: Vec<f64> = SCENARIOS
+        .iter()
+        .map(|scenario| app.display_value(app.model.predict(app.chart_x_cursor, scenario.ratio, scenario.is_direct)))
+        .collect();
+    let heights: Vec<u64> = sums.iter().map(|sum| sum.max(0.0).round() as u64).collect();
+    // Equal sums draw as equal full-height bars; the floor of 1 keeps an all-zero model from scaling by zero.
+    let highest = heights.iter().copied().max().unwrap_or(0).max(1);
+    let bars: Vec<Bar> = SCENARIOS
+        .iter()
+        .zip(sums.iter().zip(&heights))
+        .map(|(scenario, (&sum, &height))| {
+            Bar::default()
+                .label(scenario.code.into())
+                .value(height)
+                .text_value(app.short_value(sum, app.mask_results))
+        })
+        .collect();
+    let title = format!("Required Sum by Scenario at {:.0} Seats - [b/Esc to Close]", app.chart_x_cursor);
+    BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .data(BarGroup::default().bars(&bars))
+        .max(highest)
+        .bar_width(7)
+        .bar_gap(2)
+        .bar_style(app.paint(Style::default().fg(Color::Cyan)))
+        .value_style(app.paint(Style::default().fg(Color::Black).bg(Color::Cyan)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_evalexpr_usage() {
         let res = eval("100+200").unwrap().as_int().unwrap();
         assert_eq!(res, 300);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_full_width_digits_normalized() {
+        let normalized: String = "１２３".chars().filter_map(normalize_input_char).collect();
+        assert_eq!(normalized, "123");
+        assert_eq!(normalize_input_char('é'), None);
+    }
+
+    #[test]
+    fn test_round_to_granularity() {
+        assert_eq!(round_to_granularity(1234.0, 10.0), 1230.0);
+        assert_eq!(round_to_granularity(1235.0, 10.0), 1240.0);
+        assert_eq!(round_to_granularity(1236.7, 10.0), 1240.0);
+        assert_eq!(round_to_granularity(4.9, 10.0), 0.0);
+        assert_eq!(round_to_granularity(12.34, 1.0), 12.0);
+    }
+
+    #[test]
+    fn test_scenarios_follow_data_csv_columns() {
+        // data.csv: index, seats, then one column per scenario headed by its label.
+        let header = include_str!("../data.csv").lines().next().unwrap();
+        // The file's last header is missing its closing parenthesis.
+        let columns: Vec<&str> = header.split(',').skip(2).map(|c| c.trim().trim_end_matches(')')).collect();
+        let labels: Vec<&str> = SCENARIOS.iter().map(|s| s.label.trim_end_matches(')')).collect();
+        assert_eq!(columns, labels);
+
+        for scenario in &SCENARIOS {
+            assert_eq!(scenario.is_direct, scenario.key.starts_with("direct-"));
+            assert_eq!(scenario.is_direct, scenario.label.starts_with("Direct"));
+        }
+        let ratios: Vec<f64> = SCENARIOS.iter().map(|s| s.ratio).collect();
+        assert_eq!(ratios, [1.0, 1.0, 2.0, 2.0, 3.0, 3.0]);
+
+        // model_builder keeps its own copy as SCENARIO_COLUMNS; the two must agree row for row.
+        let builder = include_str!("../model_builder/src/main.rs");
+        let table = builder.split("const SCENARIO_COLUMNS").nth(1).unwrap().split("];").next().unwrap();
+        let rows: Vec<&str> = table.lines().map(str::trim).filter(|l| l.starts_with('(')).collect();
+        let expected: Vec<String> =
+            SCENARIOS.iter().map(|s| format!("(\"{}\", {:?}, {}),", s.key, s.ratio, s.is_direct)).collect();
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_validate_model() {
+        let good = PolyModel { weights: vec![1000.0, 1.0, 10.0, 5.0, 100.0] };
+        assert!(validate_model(&good).is_ok());
+
+        let broken = PolyModel { weights: vec![f64::NAN, 1.0, 10.0, 5.0, 100.0] };
+        assert!(validate_model(&broken).is_err());
+    }
+
+    #[test]
+    fn test_load_model() {
+        let good = PolyModel { weights: vec![1000.0, 1.0, 10.0, 5.0, 100.0] };
+        let bytes = bincode::serialize(&good).unwrap();
+        assert_eq!(load_model(&bytes).unwrap().weights, good.weights);
+
+        // Truncated
+        assert!(matches!(load_model(&bytes[..bytes.len() - 3]), Err(AppError::InvalidModel(_))));
+
+        // Wrong shape
+        let short = PolyModel { weights: vec![1000.0, 1.0] };
+        assert!(load_model(&bincode::serialize(&short).unwrap()).is_err());
+
+        // Not a model at all
+        assert!(load_model(b"not a model").is_err());
+    }
+
+    #[test]
+    fn test_needed_history_is_bounded() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let mut app = App::new(model.clone(), model);
+        app.my_city_input = "500".to_string();
+        app.update_calculation();
+        assert_eq!(app.needed_history.len(), 1);
+
+        for _ in 0..HISTORY_LEN * 2 {
+            app.move_cursor(10.0);
+        }
+        assert_eq!(app.needed_history.len(), HISTORY_LEN);
+    }
+
+    #[test]
+    fn test_input_is_capped() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        for _ in 0..MAX_INPUT_LEN + 5 {
+            app.insert_char('9');
+        }
+        assert_eq!(app.my_city_input.len(), MAX_INPUT_LEN);
+        assert!(app.notice.is_some());
+        assert!(app.flash);
+    }
+
+    #[test]
+    fn test_paste_inserts_filtered_text() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        app.input_mode = InputMode::Editing;
+
+        assert!(!app.handle_event(Event::Paste("１２0+5\n".to_string())));
+        assert_eq!(app.my_city_input, "120+5");
+    }
+
+    #[test]
+    fn test_mode_indicator_follows_input_mode() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        let screen = |terminal: &Terminal<ratatui::backend::TestBackend>| {
+            terminal.backend().buffer().content.iter().map(|cell| cell.symbol()).collect::<String>()
+        };
+
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert!(screen(&terminal).contains("NORMAL"));
+
+        app.handle_key(KeyCode::Char('i').into());
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert!(screen(&terminal).contains("EDITING"));
+        assert!(!screen(&terminal).contains("NORMAL"));
+    }
+
+    #[test]
+    fn test_scenario_bars_popup() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 10.0, 5.0, 100.0] };
+        let mut app = App::new(model.clone(), model);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        let screen = |terminal: &Terminal<ratatui::backend::TestBackend>| {
+            terminal.backend().buffer().content.iter().map(|cell| cell.symbol()).collect::<String>()
+        };
+
+        app.handle_key(KeyCode::Char('b').into());
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        let text = screen(&terminal);
+        assert!(text.contains("at 300 Seats"));
+        for code in ["S1", "D1", "S2", "D2", "S3", "D3"] {
+            assert!(text.contains(code), "missing {}", code);
+        }
+        // 1000 + 300 + 10r + 5r^2, plus 100 when direct.
+        assert!(text.contains("1315") && text.contains("1475"));
+
+        app.handle_key(KeyCode::Esc.into());
+        assert!(!app.show_scenario_bars);
+
+        // All-zero sums draw flat bars instead of scaling by zero.
+        let zero = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(zero.clone(), zero);
+        app.show_scenario_bars = true;
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert!(screen(&terminal).contains("D3"));
+    }
+
+    #[test]
+    fn test_minimal_ui_has_no_borders() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let mut app = App::new(model.clone(), model);
+        app.my_city_input = "500".to_string();
+        app.update_calculation();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 6)).unwrap();
+        terminal.draw(|f| ui_minimal(f, &app)).unwrap();
+
+        let screen: String = terminal.backend().buffer().content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("My City Points: 500  NORMAL"));
+        assert!(screen.contains("Seats: 300"));
+        assert!(screen.contains("Direct Needs: 900.00"));
+        assert!(!screen.contains('│') && !screen.contains('─'));
+    }
+
+    #[test]
+    fn test_resize_redraws_with_wide_layout() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let mut app = App::new(model.clone(), model);
+        app.my_city_input = "500".to_string();
+        app.update_calculation();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        // Row of the first line containing `text`.
+        let row_of = |terminal: &Terminal<ratatui::backend::TestBackend>, text: &str| {
+            let buffer = terminal.backend().buffer();
+            let cells: Vec<&str> = buffer.content.iter().map(|cell| cell.symbol()).collect();
+            cells.chunks(buffer.area.width as usize).position(|row| row.concat().contains(text))
+        };
+
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert!(row_of(&terminal, "Other City Needed") > row_of(&terminal, "My City Points"));
+
+        assert!(!app.handle_event(Event::Resize(140, 40)));
+        terminal.backend_mut().resize(140, 40);
+        terminal.draw(|f| ui(f, &app)).unwrap();
+
+        let areas = layout_areas(terminal.size().unwrap());
+        assert!(areas.chart.x >= areas.input.x + areas.input.width);
+        let chart_row = row_of(&terminal, "Other City Needed");
+        assert!(chart_row.is_some());
+        assert_eq!(chart_row, row_of(&terminal, "My City Points"));
+    }
+
+    #[test]
+    fn test_wide_layout_puts_chart_beside_inputs() {
+        let narrow = layout_areas(Rect::new(0, 0, 100, 40));
+        assert!(narrow.chart.y > narrow.input.y);
+        assert_eq!(narrow.chart.x, narrow.input.x);
+
+        let wide = layout_areas(Rect::new(0, 0, WIDE_LAYOUT_MIN_WIDTH, 40));
+        assert!(wide.chart.x > wide.input.x + wide.input.width - 1);
+        assert_eq!(wide.chart.y, wide.input.y);
+    }
+
+    #[test]
+    fn test_notation_toggle() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        assert_eq!(app.format_value(1234.5678), "1234.57");
+
+        app.handle_key(KeyCode::Char('e').into());
+        assert_eq!(app.notation, Notation::Scientific);
+        assert_eq!(app.format_value(1234.5678), "1.235e3");
+    }
+
+    #[test]
+    fn test_scaled_display() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        app.scale = 1000.0;
+        app.suffix = "k".to_string();
+        assert_eq!(app.format_value(300.0), "0.30k");
+        assert_eq!(app.short_value(1250.0, false), "1.25k");
+        assert_eq!(app.short_value(1250.0, true), "****k");
+    }
+
+    #[test]
+    fn test_points_per_seat() {
+        assert_eq!(points_per_seat(300.0, 150.0), Some(2.0));
+        assert_eq!(points_per_seat(300.0, 0.0), None);
+
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        app.granularity = Some(10.0);
+        app.scale = 1000.0;
+        app.suffix = "k".to_string();
+        assert_eq!(app.format_per_seat(1400.0 / 300.0), "4.67");
+        assert_eq!(app.format_value(1400.0), "1.40k");
+    }
+
+    #[test]
+    fn test_split_gauge_honours_granularity() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let mut app = App::new(model.clone(), model);
+        app.granularity = Some(10.0);
+        app.my_city_input = "503".to_string();
+        app.update_calculation();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+
+        // Direct at 300 seats needs 1400 in total, so 897 from the other city.
+        let screen: String = terminal.backend().buffer().content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("Other 900"), "{}", screen);
+        assert!(screen.contains("= 1400"));
+        assert!(!screen.contains("Other 897"));
+    }
+
+    #[test]
+    fn test_no_color_draws_without_colors() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 10.0, 5.0, 100.0] };
+        let mut app = App::new(model.clone(), model);
+        app.my_city_input = "500".to_string();
+        app.update_calculation();
+        app.color = false;
+        app.show_scenario_bars = true;
+        app.error_popup = Some("boom".to_string());
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+
+        let cells = &terminal.backend().buffer().content;
+        assert!(cells.iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+
+        app.color = true;
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert!(terminal.backend().buffer().content.iter().any(|cell| cell.fg != Color::Reset));
+    }
+
+    #[test]
+    fn test_split_percent() {
+        assert_eq!(split_percent(630.0, 1000.0), Some((63.0, 37.0)));
+        assert_eq!(split_percent(1500.0, 1000.0), Some((100.0, 0.0)));
+        assert_eq!(split_percent(500.0, 0.0), None);
+        assert_eq!(split_percent(500.0, -10.0), None);
+    }
+
+    #[test]
+    fn test_needed_color_scales_with_share() {
+        assert_eq!(needed_color(0.0, 500.0), Color::Green);
+        let Color::Rgb(small, ..) = needed_color(100.0, 500.0) else { panic!("expected rgb") };
+        let Color::Rgb(large, ..) = needed_color(5000.0, 500.0) else { panic!("expected rgb") };
+        assert!(small < large);
+    }
+
+    #[test]
+    fn test_leading_plus_is_accepted_once() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        app.my_city_input = "+180".to_string();
+        app.update_calculation();
+        assert_eq!(app.p1_value, Some(180.0));
+
+        for input in ["++180", "18+"] {
+            assert!(eval(strip_leading_plus(input)).is_err(), "{} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn test_masking_hides_digits() {
+        assert_eq!(masked("1234.5".to_string(), true), "******");
+        assert_eq!(masked("1234.5".to_string(), false), "1234.5");
+    }
+
+    #[test]
+    fn test_scenario_equations() {
+        let model = PolyModel { weights: vec![500.0, 1.5, 20.0, 10.0, 150.0] };
+
+        let (slope, intercept) = scenario_equation(&model, &SCENARIOS[3]);
+        assert_eq!(SCENARIOS[3].key, "direct-twice");
+        assert!((slope - 1.5).abs() < 1e-9);
+        assert!((intercept - (500.0 + 2.0 * 20.0 + 4.0 * 10.0 + 150.0)).abs() < 1e-9);
+
+        assert_eq!(equations_report(&model).lines().count(), SCENARIOS.len() + 1);
+        assert_eq!(
+            route_gap_note(&model),
+            "Direct requires 150.00 more than stopover at every ratio; they never break even."
+        );
+        assert_eq!(
+            scenarios_report().lines().nth(3),
+            Some("direct-twice\t2\tdirect\tDirect (One City Twice as Big)")
+        );
+    }
+
+    #[test]
+    fn test_curve_csv() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let csv = curve_csv(&model, true, 500.0, 100.0, 300.0, 100.0).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows, ["seats,required_sum,other_city_needed", "100,1200.00,700.00", "200,1300.00,800.00", "300,1400.00,900.00"]);
+
+        assert!(curve_csv(&model, true, 500.0, 100.0, 300.0, 0.0).is_err());
+        assert!(curve_csv(&model, true, 500.0, 300.0, 100.0, 10.0).is_err());
+        assert!(curve_csv(&model, true, 500.0, 0.0, 720.0, 1e-6).is_err());
+
+        let args = |format: &str| ["curve", "--route", "direct", "--my-points", "500", "--format", format].map(String::from);
+        assert!(Options::parse(args("csv").into_iter()).is_ok());
+        assert!(Options::parse(args("json").into_iter()).unwrap_err().contains("--format"));
+    }
+
+    #[test]
+    fn test_diff_models_finds_largest_shift() {
+        let a = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        assert_eq!(diff_models(&a, &a).max_abs, 0.0);
+
+        let mut b = a.clone();
+        b.weights[1] = 1.5;
+        let diff = diff_models(&a, &b);
+        assert!((diff.max_abs - 360.0).abs() < 1e-9);
+        assert!((diff.mean_abs - 180.0).abs() < 1e-9);
+        assert_eq!((diff.worst_scenario, diff.worst_seats), ("stopover-same", 720.0));
+        assert!(diff.json().starts_with("{\"max_abs\":360,"));
+    }
+
+    #[test]
+    fn test_diff_rejects_non_finite_model() {
+        let dir = std::env::temp_dir();
+        let good = dir.join(format!("ato3cal_diff_good_{}.bin", std::process::id())).to_string_lossy().into_owned();
+        let nan = dir.join(format!("ato3cal_diff_nan_{}.bin", std::process::id())).to_string_lossy().into_owned();
+        let weights = vec![1000.0, 1.0, 0.0, 0.0, 100.0];
+        std::fs::write(&good, bincode::serialize(&PolyModel { weights: weights.clone() }).unwrap()).unwrap();
+        // Written directly: save_model would refuse it.
+        let mut broken = weights;
+        broken[2] = f64::NAN;
+        std::fs::write(&nan, bincode::serialize(&PolyModel { weights: broken }).unwrap()).unwrap();
+
+        let args = ["diff", "--a", &good, "--b", &nan].map(String::from);
+        let err = run(Options::parse(args.into_iter()).unwrap()).unwrap_err();
+        assert_eq!(exit_code(err.as_ref()), EXIT_MODEL);
+        assert!(err.to_string().contains("non-finite"), "{}", err);
+
+        std::fs::remove_file(&good).unwrap();
+        std::fs::remove_file(&nan).unwrap();
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let usage: Box<dyn Error> = AppError::Usage(Options::parse(["--bogus".to_string()].into_iter()).unwrap_err()).into();
+        assert_eq!(exit_code(usage.as_ref()), EXIT_USAGE);
+
+        let corrupt = load_model(b"junk").unwrap_err();
+        assert_eq!(exit_code(&corrupt), EXIT_MODEL);
+        let missing = load_model_from_path("/nonexistent-dir/model.bin").unwrap_err();
+        assert_eq!(exit_code(&missing), EXIT_MODEL);
+
+        let other: Box<dyn Error> = "terminal went away".into();
+        assert_eq!(exit_code(other.as_ref()), 1);
+    }
+
+    #[test]
+    fn test_ctrl_c_quits_from_any_mode() {
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+
+        assert!(app.handle_key(ctrl_c));
+        app.input_mode = InputMode::Editing;
+        assert!(app.handle_key(ctrl_c));
+        assert!(!app.handle_key(KeyCode::Char('c').into()));
+        assert_eq!(app.my_city_input, "c");
+    }
+
+    #[test]
+    fn test_other_city_needed() {
+        // No ratio terms: required sum is 1000 + seats (+100 direct).
+        let model = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+
+        let needed = other_city_needed(&model, 200.0, 500.0, false).unwrap();
+        assert!((needed - 700.0).abs() < 1e-6);
+        let needed = other_city_needed(&model, 200.0, 500.0, true).unwrap();
+        assert!((needed - 800.0).abs() < 1e-6);
+
+        // My city alone covers it.
+        assert!(other_city_needed(&model, 200.0, 5000.0, true).unwrap() < 1e-6);
+
+        // Out of reach within the search range.
+        let huge = PolyModel { weights: vec![1e9, 0.0, 0.0, 0.0, 0.0] };
+        assert_eq!(other_city_needed(&huge, 200.0, 500.0, false), None);
+    }
+
+    #[test]
+    fn test_weight_overrides() {
+        assert_eq!(parse_weight_override("3=40"), Ok((3, 40.0)));
+        assert_eq!(parse_weight_override("ratio^2=-1.5"), Ok((3, -1.5)));
+        assert!(parse_weight_override("5=1").is_err());
+        assert!(parse_weight_override("Seats").is_err());
+        for value in ["nan", "inf", "-inf"] {
+            assert!(parse_weight_override(&format!("1={}", value)).is_err());
+        }
+
+        let embedded = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let loaded = PolyModel { weights: vec![900.0, 2.0, 0.0, 0.0, 50.0] };
+        let mut app = App::new(embedded, loaded);
+        app.set_overrides(vec![(1, 3.0)]);
+        assert!(app.is_modified());
+        assert_eq!(app.model.weights, vec![900.0, 3.0, 0.0, 0.0, 50.0]);
+
+        // Reverting swaps the base model but keeps the experiment applied.
+        app.revert_model();
+        assert_eq!(app.model.weights, vec![1000.0, 3.0, 0.0, 0.0, 100.0]);
+
+        app.clear_overrides();
+        assert!(!app.is_modified());
+        assert_eq!(app.model.weights, vec![1000.0, 1.0, 0.0, 0.0, 100.0]);
+    }
+
+    #[test]
+    fn test_save_model_round_trips() {
+        let model = PolyModel { weights: vec![1000.0, 1.0, 10.0, 5.0, 100.0] };
+        let path = std::env::temp_dir().join(format!("ato3cal_save_{}.bin", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+
+        save_model(&model, &path).unwrap();
+        assert_eq!(load_model_from_path(&path).unwrap().weights, model.weights);
+        std::fs::remove_file(&path).unwrap();
+
+        let broken = PolyModel { weights: vec![1000.0, f64::NAN, 10.0, 5.0, 100.0] };
+        assert!(matches!(save_model(&broken, &path), Err(AppError::InvalidModel(_))));
+        assert!(!std::path::Path::new(&path).exists());
+
+        let mut app = App::new(model.clone(), model);
+        app.save_path_input = "/nonexistent-dir/model.bin".to_string();
+        app.finish_save();
+        assert!(app.error_popup.is_some());
+    }
+
+    #[test]
+    fn test_revert_to_embedded_model() {
+        let embedded = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let loaded = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(embedded.clone(), loaded);
+
+        app.revert_model();
+        assert_eq!(app.model.weights, embedded.weights);
+        assert_eq!(app.notice.as_deref(), Some("Reverted to built-in model."));
+    }
+
+    #[test]
+    fn test_reset_state_reverts_and_clears_overrides() {
+        let embedded = PolyModel { weights: vec![1000.0, 1.0, 0.0, 0.0, 100.0] };
+        let loaded = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(embedded.clone(), loaded);
+        app.set_overrides(vec![(0, 5.0)]);
+
+        assert!(!app.handle_key(KeyCode::Char('r').into()));
+        assert_eq!(app.model.weights, embedded.weights);
+        assert!(app.overrides.is_empty());
+        assert!(!app.is_modified());
+        assert_eq!(app.notice.as_deref(), Some("State reset."));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_solve_p2_balances_required_sum(
+            w0 in 0.0..2000.0f64,
+            w1 in 0.0..5.0f64,
+            w2 in -200.0..200.0f64,
+            w3 in -100.0..100.0f64,
+            w4 in 0.0..300.0f64,
+            p1 in 1.0..10_000.0f64,
+            seats in 0.0..720.0f64,
+            is_direct in any::<bool>(),
+        ) {
+            let model = PolyModel { weights: vec![w0, w1, w2, w3, w4] };
+            let app = App::new(model.clone(), model);
+
+            if let Some(p2) = app.solve_p2(p1, seats, is_direct) {
+                prop_assert!(p2.is_finite() && p2 >= 0.0);
+
+                let required = app.required_sum(p1, p2, seats, is_direct);
+                prop_assert!(required.is_finite());
+                prop_assert!(p1 + p2 >= required);
+
+                // Unless my city already covers it, the other city supplies exactly the shortfall.
+                if p2 > 1e-6 {
+                    prop_assert!((required - p1 - p2).abs() < 1e-2, "required {} vs {} + {}", required, p1, p2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_watch_reloads_after_file_settles() {
+        let path = std::env::temp_dir().join(format!("ato3cal_watch_{}.bin", std::process::id()));
+        let model = PolyModel { weights: vec![0.0; 5] };
+        let mut app = App::new(model.clone(), model);
+        app.watch = Some(ModelWatch { path: path.to_string_lossy().into_owned(), loaded: None, pending: None });
+
+        std::fs::write(&path, b"partial").unwrap();
+        app.poll_model_file();
+        assert!(app.notice.is_none());
+        app.poll_model_file();
+        assert!(app.notice.as_deref().unwrap().starts_with("Reload failed"));
+        assert_eq!(app.model.weights, vec![0.0; 5]);
+
+        let reloaded = PolyModel { weights: vec![1.0, 2.0, 3.0, 4.0, 5.0] };
+        save_model(&reloaded, &path.to_string_lossy()).unwrap();
+        app.watch.as_mut().unwrap().loaded = None;
+        app.poll_model_file();
+        app.poll_model_file();
+        assert_eq!(app.notice.as_deref(), Some("Model reloaded."));
+        assert_eq!(app.model.weights, reloaded.weights);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}